@@ -333,6 +333,55 @@ pub mod walls {
     }
 }
 
+pub mod turrets {
+    use bevy::prelude::*;
+    use bevy_asset_loader::prelude::*;
+
+    use crate::GameState;
+
+    #[derive(Resource, AssetCollection)]
+    pub struct TurretSprites {
+        #[asset(path = "objects/turret/towerBase.png")]
+        pub base: Handle<Image>,
+        #[asset(path = "objects/turret/machineGun.png")]
+        pub gun: Handle<Image>,
+    }
+
+    pub struct TurretAssetPlugin;
+
+    impl Plugin for TurretAssetPlugin {
+        fn build(&self, app: &mut App) {
+            app.configure_loading_state(
+                LoadingStateConfig::new(GameState::Loading).load_collection::<TurretSprites>(),
+            );
+        }
+    }
+}
+
+pub mod waves {
+    use bevy::prelude::*;
+    use bevy_asset_loader::prelude::*;
+
+    use crate::waves::WaveDefinitions;
+    use crate::GameState;
+
+    #[derive(Resource, AssetCollection)]
+    pub struct WaveAssets {
+        #[asset(path = "enemy.waves.ron")]
+        pub definitions: Handle<WaveDefinitions>,
+    }
+
+    pub struct WaveAssetPlugin;
+
+    impl Plugin for WaveAssetPlugin {
+        fn build(&self, app: &mut App) {
+            app.configure_loading_state(
+                LoadingStateConfig::new(GameState::Loading).load_collection::<WaveAssets>(),
+            );
+        }
+    }
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct GroundBase {
     #[asset(texture_atlas(tile_size_x = 200., tile_size_y = 200., columns = 5, rows = 3,))]
@@ -360,8 +409,9 @@ pub enum DirtTile {
     OutsideBottomRight = 9,
 }
 
+/// Every sprite sheet a pawn can be drawn with, split by `Gender`
 #[derive(AssetCollection, Resource)]
-pub struct MalePawns {
+pub struct PawnAppearance {
     #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 16., columns = 4, rows = 4))]
     #[asset(path = "objects/pawns/male/M_01.png")]
     pub male1: Handle<TextureAtlas>,
@@ -398,25 +448,119 @@ pub struct MalePawns {
     #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 16., columns = 4, rows = 4))]
     #[asset(path = "objects/pawns/male/M_12.png")]
     pub male12: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_01.png")]
+    pub female1: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_02.png")]
+    pub female2: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_03.png")]
+    pub female3: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_04.png")]
+    pub female4: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_05.png")]
+    pub female5: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_06.png")]
+    pub female6: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_07.png")]
+    pub female7: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_08.png")]
+    pub female8: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_09.png")]
+    pub female9: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_10.png")]
+    pub female10: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_11.png")]
+    pub female11: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 17., columns = 4, rows = 3))]
+    #[asset(path = "objects/pawns/female/F_12.png")]
+    pub female12: Handle<TextureAtlas>,
+}
+
+impl PawnAppearance {
+    /// How many sprite sheets there are for each gender
+    pub const SHEETS: usize = 12;
+
+    fn sheets(&self, gender: Gender) -> [&Handle<TextureAtlas>; Self::SHEETS] {
+        match gender {
+            Gender::Male => [
+                &self.male1,
+                &self.male2,
+                &self.male3,
+                &self.male4,
+                &self.male5,
+                &self.male6,
+                &self.male7,
+                &self.male8,
+                &self.male9,
+                &self.male10,
+                &self.male11,
+                &self.male12,
+            ],
+            Gender::Female => [
+                &self.female1,
+                &self.female2,
+                &self.female3,
+                &self.female4,
+                &self.female5,
+                &self.female6,
+                &self.female7,
+                &self.female8,
+                &self.female9,
+                &self.female10,
+                &self.female11,
+                &self.female12,
+            ],
+        }
+    }
+
+    pub fn get(&self, appearance: Appearance) -> Handle<TextureAtlas> {
+        self.sheets(appearance.gender)[appearance.sheet % Self::SHEETS].clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum Gender {
+    Male,
+    Female,
+}
+
+impl Gender {
+    /// How many rows of walking frames this gender's sprite sheets have. Each row has a frame for every
+    /// `CharacterFacing`.
+    pub fn frame_rows(&self) -> usize {
+        match self {
+            Gender::Male => 4,
+            Gender::Female => 3,
+        }
+    }
+}
+
+/// Which of the `PawnAppearance` sprite sheets a pawn is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct Appearance {
+    pub gender: Gender,
+    pub sheet: usize,
 }
 
-impl MalePawns {
-    pub fn get_random(&self) -> Handle<TextureAtlas> {
-        let random = rand::thread_rng().gen_range(1..12);
-        match random {
-            1 => self.male1.clone(),
-            2 => self.male2.clone(),
-            3 => self.male3.clone(),
-            4 => self.male4.clone(),
-            5 => self.male5.clone(),
-            6 => self.male6.clone(),
-            7 => self.male7.clone(),
-            8 => self.male8.clone(),
-            9 => self.male9.clone(),
-            10 => self.male10.clone(),
-            11 => self.male11.clone(),
-            12 => self.male12.clone(),
-            _ => self.male1.clone(),
+impl Appearance {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            gender: if rng.gen_bool(0.5) {
+                Gender::Male
+            } else {
+                Gender::Female
+            },
+            sheet: rng.gen_range(0..PawnAppearance::SHEETS),
         }
     }
 }
@@ -434,11 +578,17 @@ pub struct GameAssets;
 
 impl Plugin for GameAssets {
     fn build(&self, app: &mut App) {
-        app.add_plugins((rocks::RockPlugin, trees::TreePlugin, walls::WallPlugin))
-            .configure_loading_state(
-                LoadingStateConfig::new(GameState::Loading)
-                    .load_collection::<GroundBase>()
-                    .load_collection::<MalePawns>(),
-            );
+        app.add_plugins((
+            rocks::RockPlugin,
+            trees::TreePlugin,
+            walls::WallPlugin,
+            turrets::TurretAssetPlugin,
+            waves::WaveAssetPlugin,
+        ))
+        .configure_loading_state(
+            LoadingStateConfig::new(GameState::Loading)
+                .load_collection::<GroundBase>()
+                .load_collection::<PawnAppearance>(),
+        );
     }
 }
@@ -1,7 +1,29 @@
+use crate::navmesh::FlowField;
 use bevy::prelude::*;
 
+/// The factory covers a square of this many tiles, anchored at its bottom left tile
+pub const FACTORY_SIZE: usize = 4;
+pub const FACTORY_MAX_HEALTH: usize = 1000;
+
 #[derive(Component)]
 pub struct Placed;
 
 #[derive(Component)]
 pub struct Factory;
+
+/// Integration field leading every tile on the map to the factory footprint. Enemies follow it
+/// instead of pathfinding to the factory individually.
+#[derive(Resource, Default)]
+pub struct FactoryFlowField(pub Option<FlowField>);
+
+/// Every tile covered by a factory whose bottom left corner is on `bottom_left`
+pub fn factory_footprint(bottom_left: Vec2) -> impl Iterator<Item = Vec2> {
+    (0..FACTORY_SIZE).flat_map(move |offset_x| {
+        (0..FACTORY_SIZE).map(move |offset_y| {
+            Vec2::new(
+                bottom_left.x + offset_x as f32,
+                bottom_left.y + offset_y as f32,
+            )
+        })
+    })
+}
@@ -10,19 +10,40 @@ pub struct FactoryPlugin;
 
 impl Plugin for FactoryPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(GameState::WorldSpawn),
-            systems::initial_spawn_factory,
-        )
-        .add_systems(
-            Update,
-            (
-                systems::clamp_factory_to_cursor_position
-                    .run_if(in_state(GameState::FactoryPlacement)),
-                systems::place_factory
-                    .after(systems::clamp_factory_to_cursor_position)
-                    .run_if(in_state(GameState::FactoryPlacement)),
-            ),
-        );
+        app.init_resource::<FactoryFlowField>()
+            .add_systems(
+                OnEnter(GameState::WorldSpawn),
+                systems::initial_spawn_factory,
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                systems::reset_factory_flow_field,
+            )
+            .add_systems(
+                Update,
+                systems::handle_destroyed_factory
+                    .after(crate::health::HealthSystemSet)
+                    .run_if(in_state(GameState::Main)),
+            )
+            .add_systems(
+                Update,
+                (
+                    systems::clamp_factory_to_cursor_position
+                        .run_if(in_state(GameState::FactoryPlacement)),
+                    systems::place_factory
+                        .after(systems::clamp_factory_to_cursor_position)
+                        .run_if(in_state(GameState::FactoryPlacement)),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    systems::build_factory_flow_field,
+                    systems::update_factory_flow_field
+                        .run_if(resource_changed::<crate::navmesh::Navmesh>()),
+                )
+                    .chain()
+                    .in_set(crate::navmesh::NavmeshSystemSet::Update),
+            );
     }
 }
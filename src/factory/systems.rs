@@ -1,11 +1,14 @@
 use super::components::*;
+use crate::health::{EntityDestroyed, Health, HealthBundle};
+use crate::navmesh::{
+    components::{NavTileChanged, NavmeshSettings},
+    FlowField, Navmesh, NavmeshMut,
+};
 use crate::utils::*;
-use crate::{navmesh, CursorPosition, GameState, GameTile};
+use crate::{navmesh, CursorPosition, GameState, GameTile, TILE_SIZE};
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
 
-const FACTORY_SIZE: usize = 4;
-
 pub fn initial_spawn_factory(
     mut commands: Commands,
     cursor_position: Res<CursorPosition>,
@@ -50,7 +53,7 @@ pub fn place_factory(
     q_factory: Query<(Entity, &GlobalTransform), (With<Factory>, Without<Placed>)>,
     input: Query<&ActionState<crate::Input>>,
     mut game_state: ResMut<NextState<GameState>>,
-    mut navmesh: ResMut<navmesh::components::Navmesh>,
+    mut navmesh: NavmeshMut,
 ) {
     let Ok((factory_entity, factory_transform)) = q_factory.get_single() else {
         return;
@@ -67,30 +70,80 @@ pub fn place_factory(
             return;
         }
 
-        commands.entity(factory_entity).insert((Placed, GameTile));
+        commands
+            .entity(factory_entity)
+            .insert((Placed, GameTile, Health::new(FACTORY_MAX_HEALTH)));
+        let factory_width = FACTORY_SIZE as f32 * TILE_SIZE;
+        commands
+            .spawn(HealthBundle::above(factory_width, factory_width))
+            .set_parent(factory_entity);
         commands.entity(factory_entity).remove::<AabbGizmo>();
         game_state.set(GameState::PawnSpawn);
 
         // mark navmesh tiles as occupied
-        for x in x as usize..x as usize + FACTORY_SIZE {
-            for y in y as usize..y as usize + FACTORY_SIZE {
-                navmesh.0[x][y].walkable = false;
+        for tile in factory_footprint(Vec2::new(x, y)) {
+            if let Err(error) = navmesh.set_walkable(GridPos::from_tile_pos_vec(tile), false) {
+                warn!("Unable to add factory to the navmesh: {error}");
             }
         }
     }
 }
 
+pub fn build_factory_flow_field(
+    q_factory: Query<&GlobalTransform, (With<Factory>, Added<Placed>)>,
+    navmesh: Res<Navmesh>,
+    settings: Res<NavmeshSettings>,
+    mut flow_field: ResMut<FactoryFlowField>,
+) {
+    let Ok(factory_transform) = q_factory.get_single() else {
+        return;
+    };
+
+    let footprint = factory_footprint(factory_transform.translation().world_pos_to_tile());
+
+    flow_field.0 = Some(FlowField::new(&navmesh, &settings, footprint));
+}
+
+pub fn update_factory_flow_field(
+    navmesh: Res<Navmesh>,
+    settings: Res<NavmeshSettings>,
+    mut flow_field: ResMut<FactoryFlowField>,
+    mut nav_tile_changes: EventReader<NavTileChanged>,
+) {
+    let changed = nav_tile_changes
+        .read()
+        .map(|change| change.pos)
+        .collect::<Vec<_>>();
+    let Some(flow_field) = &mut flow_field.0 else {
+        return;
+    };
+
+    flow_field.update(&navmesh, &settings, changed);
+}
+
 fn check_spawn_bounds_by_navtiles(navmesh: &navmesh::components::Navmesh, x: f32, y: f32) -> bool {
-    let mut is_valid = true;
+    // check navmesh bounds for non-walkable tiles assuming the factory is anchored in the bottom left.
+    // Tiles hanging off the edge of the map are never valid.
+    factory_footprint(Vec2::new(x, y)).all(|tile| {
+        navmesh
+            .get(GridPos::from_tile_pos_vec(tile))
+            .is_ok_and(|nav_tile| nav_tile.walkable)
+    })
+}
 
-    // check navmesh bounds for non-walkable tiles assuming the factory is anchored in the bottom left
-    for x in x as usize..x as usize + FACTORY_SIZE {
-        for y in y as usize..y as usize + FACTORY_SIZE {
-            if !navmesh.0[x][y].walkable {
-                is_valid = false;
-            }
+/// Losing the factory loses the game
+pub fn handle_destroyed_factory(
+    mut destroyed_events: EventReader<EntityDestroyed>,
+    q_factory: Query<(), With<Factory>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for event in destroyed_events.read() {
+        if q_factory.contains(event.entity) {
+            game_state.set(GameState::GameOver);
         }
     }
+}
 
-    is_valid
+pub fn reset_factory_flow_field(mut commands: Commands) {
+    commands.insert_resource(FactoryFlowField::default());
 }
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+#[derive(Component, Reflect, Debug, Clone)]
+pub struct Health {
+    pub current: usize,
+    pub max: usize,
+}
+
+impl Health {
+    pub fn new(max: usize) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current as f32 / self.max.max(1) as f32
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.current >= self.max
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum DamageType {
+    Melee,
+    Projectile,
+}
+
+/// The bar drawn over an entity with `Health`. It shrinks from `width` as the entity loses health and is
+/// hidden while the entity is unharmed.
+#[derive(Component)]
+pub struct HealthBar {
+    pub width: f32,
+}
+
+#[derive(Bundle)]
+pub struct HealthBundle {
+    pub health_bundle: SpriteBundle,
+    pub health_bar: HealthBar,
+}
+
+impl HealthBundle {
+    /// A health bar `width` pixels wide, floating just above an entity `height` pixels tall. Entities are
+    /// anchored at their bottom left corner.
+    pub fn above(width: f32, height: f32) -> Self {
+        Self {
+            health_bar: HealthBar { width },
+            health_bundle: SpriteBundle {
+                transform: Transform::from_xyz(width / 2., height + 4., 1.),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(width, 2.)),
+                    color: Color::NONE,
+                    ..default()
+                },
+                ..default()
+            },
+        }
+    }
+}
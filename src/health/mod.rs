@@ -0,0 +1,46 @@
+pub mod components;
+mod systems;
+
+use crate::GameState;
+use bevy::prelude::*;
+
+pub use components::*;
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Health>()
+            .add_event::<DamageEvent>()
+            .add_event::<EntityDestroyed>()
+            .add_systems(
+                Update,
+                (systems::apply_damage, systems::update_health_ui)
+                    .chain()
+                    .in_set(HealthSystemSet)
+                    .run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+/// Damage is applied in this set. Systems dealing damage should run before it and systems cleaning up
+/// destroyed entities after it.
+#[derive(SystemSet, Hash, Debug, Clone, Eq, PartialEq)]
+pub struct HealthSystemSet;
+
+/// `amount` damage dealt to `target` by `source`, which may be a pawn, a turret or anything else
+#[derive(Event, Debug)]
+pub struct DamageEvent {
+    pub source: Entity,
+    pub target: Entity,
+    pub amount: usize,
+    pub damage_type: DamageType,
+}
+
+/// Sent once when an entity's `Health` runs out. The entity is left in the world for whichever plugin owns
+/// it to clean up.
+#[derive(Event, Debug)]
+pub struct EntityDestroyed {
+    pub entity: Entity,
+    pub destroyed_by: Entity,
+}
@@ -0,0 +1,57 @@
+use super::{components::*, DamageEvent, EntityDestroyed};
+use bevy::prelude::*;
+
+pub fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut q_health: Query<&mut Health>,
+    mut destroyed_writer: EventWriter<EntityDestroyed>,
+) {
+    for event in damage_events.read() {
+        let Ok(mut health) = q_health.get_mut(event.target) else {
+            continue;
+        };
+
+        // already destroyed and waiting to be cleaned up
+        if health.is_dead() {
+            continue;
+        }
+
+        health.current = health.current.saturating_sub(event.amount);
+        if health.is_dead() {
+            debug!(
+                "{:?} destroyed by {:?} ({:?} damage)",
+                event.target, event.source, event.damage_type
+            );
+            destroyed_writer.send(EntityDestroyed {
+                entity: event.target,
+                destroyed_by: event.source,
+            });
+        }
+    }
+}
+
+pub fn update_health_ui(
+    q_health: Query<&Health>,
+    mut q_health_bar: Query<(&Parent, &HealthBar, &mut Sprite)>,
+) {
+    for (parent, health_bar, mut sprite) in &mut q_health_bar {
+        let Ok(health) = q_health.get(parent.get()) else {
+            continue;
+        };
+
+        let fraction = health.fraction();
+        sprite.custom_size = Some(Vec2::new(fraction * health_bar.width, 2.));
+
+        sprite.color = if health.is_full() {
+            Color::NONE
+        } else if fraction > 0.75 {
+            Color::GREEN
+        } else if fraction > 0.5 {
+            Color::YELLOW
+        } else if fraction > 0.25 {
+            Color::RED
+        } else {
+            Color::rgb(0.5, 0., 0.)
+        };
+    }
+}
@@ -3,6 +3,7 @@
 
 mod assets;
 mod factory;
+mod health;
 mod navmesh;
 mod pawn;
 mod placeable;
@@ -11,6 +12,7 @@ mod stone;
 mod ui;
 mod utils;
 mod turret;
+mod waves;
 
 use assets::{DirtTile, GameAssets, GroundBase};
 use bevy::{asset::AssetMetaCheck, prelude::*, window::PrimaryWindow};
@@ -40,6 +42,7 @@ pub enum GameState {
     PawnSpawn,
     Main,
     Paused,
+    GameOver,
 }
 
 #[derive(States, Debug, Hash, PartialEq, Eq, Clone)]
@@ -60,6 +63,7 @@ pub enum Input {
     Zoom,
     Select,
     Interact,
+    Deconstruct,
     Debug,
     DebugSpawnPawn,
     Pause,
@@ -99,8 +103,12 @@ fn main() {
             navmesh::NavmeshPlugin,
             placeable::PlaceablePlugin,
             selectable::SelectablePlugin,
+            turret::TurretPlugin,
+            health::HealthPlugin,
+            waves::WavePlugin,
         ))
         .add_systems(OnEnter(GameState::WorldSpawn), build_map)
+        .add_systems(OnExit(GameState::GameOver), clear_world)
         .add_systems(
             Update,
             (
@@ -123,6 +131,7 @@ fn main() {
         .init_resource::<WorldNoise>()
         .init_resource::<CursorPosition>()
         .init_resource::<GameResources>()
+        .init_resource::<GameStats>()
         .run();
 }
 
@@ -165,6 +174,13 @@ pub struct GameResources {
     pub pawns: usize,
 }
 
+/// Running totals for the game over summary
+#[derive(Resource, Debug, Default)]
+pub struct GameStats {
+    pub stone_mined: usize,
+    pub pawns_lost: usize,
+}
+
 #[derive(Resource, Default)]
 pub struct CursorPosition(pub Option<Vec2>);
 
@@ -177,6 +193,27 @@ enum TileType {
     Grass,
 }
 
+/// Top level entities which belong to the world, rather than the window or the UI
+type WorldRootFilter = (Without<Parent>, Without<Window>, Without<Node>);
+
+/// Throw away the lost game so that `build_map` can generate a fresh one. Every plugin resets its own
+/// resources when leaving `GameState::GameOver`, the world entities are all despawned here. UI is left to
+/// the UI plugins, which already tear down their own nodes.
+fn clear_world(
+    mut commands: Commands,
+    q_world: Query<Entity, WorldRootFilter>,
+    mut world_interaction: ResMut<NextState<WorldInteraction>>,
+) {
+    for entity in &q_world {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.insert_resource(WorldNoise::default());
+    commands.insert_resource(GameResources::default());
+    commands.insert_resource(GameStats::default());
+    world_interaction.set(WorldInteraction::Selecting);
+}
+
 pub fn build_map(
     mut commands: Commands,
     mut world_noise: ResMut<WorldNoise>,
@@ -221,6 +258,7 @@ pub fn build_map(
                 )
                 .insert(MouseButton::Left, Input::Select)
                 .insert(MouseButton::Right, Input::Interact)
+                .insert(KeyCode::X, Input::Deconstruct)
                 .insert(KeyCode::Grave, Input::Debug)
                 .insert(KeyCode::Escape, Input::Pause)
                 .insert(KeyCode::Numpad0, Input::DebugSpawnPawn)
@@ -362,7 +400,9 @@ fn spawn_world_tiles(
         for y in 0..SIZE {
             let seed_value = &base_world[x][y];
 
-            let nav_tile = &mut navmesh.0[x][y];
+            let Ok(nav_tile) = navmesh.get_mut(utils::GridPos::new(x as i32, y as i32)) else {
+                continue;
+            };
 
             if (&DIRT_CUTOFF..&GRASS_CUTOFF).contains(&seed_value) {
                 // Dirt
@@ -1,18 +1,89 @@
+use super::hierarchy::NavmeshHierarchy;
+use crate::utils::GridPos;
 use crate::SIZE;
-use bevy::{prelude::*, utils::HashSet};
+use bevy::{
+    prelude::*,
+    tasks::Task,
+    utils::{HashMap, HashSet},
+};
+use std::{collections::VecDeque, sync::Arc};
+
+/// The cheapest weight a walkable tile can have. Pathfinding heuristics scale by this so they stay admissible.
+pub const MIN_TILE_WEIGHT: f32 = 1.;
+/// The widest agent, in tiles, that clearance is kept for. Clearance is capped at this, so a tile changing
+/// only affects the clearance of tiles at most this far below and to the left of it. Wider agents fit nowhere.
+pub const MAX_AGENT_SIZE: u32 = 4;
 
 #[derive(Debug, Default, Resource)]
 pub struct ToggleNavmeshDebug(pub bool);
 
-#[derive(Debug, Default)]
+/// Marks the debug overlay text showing how well the `PathCache` is doing
+#[derive(Component)]
+pub struct PathCacheDebugText;
+
+#[derive(Debug, Clone, Resource)]
+pub struct NavmeshSettings {
+    /// Allow paths to step diagonally between tiles. Diagonal steps never cut across a non-walkable corner.
+    pub allow_diagonals: bool,
+    /// The maximum number of pathfinding tasks dispatched to the async compute pool each frame.
+    pub pathfinding_budget: usize,
+}
+
+impl Default for NavmeshSettings {
+    fn default() -> Self {
+        Self {
+            allow_diagonals: true,
+            pathfinding_budget: 32,
+        }
+    }
+}
+
+/// Which side an agent is on. Some tiles, such as doors, can only be walked through by one faction.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Faction {
+    #[default]
+    Colonist,
+    Enemy,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct NavTileOccupant {
     pub weight: f32,
     pub occupied_by: HashSet<Entity>,
     pub walkable: bool,
+    /// Factions that may walk through this tile even though it is not `walkable` for everyone
+    pub passable_by: HashSet<Faction>,
+    /// The width of the largest square of walkable tiles with this tile as its bottom left corner, so an
+    /// agent that many tiles wide fits here. Capped at `MAX_AGENT_SIZE`, and kept up to date by
+    /// `update_clearance_around` as tiles change walkability.
+    pub clearance: u32,
+}
+
+impl NavTileOccupant {
+    pub fn is_walkable_for(&self, faction: Faction) -> bool {
+        self.walkable || self.passable_by.contains(&faction)
+    }
+}
+
+/// The grid of nav tiles covering the map. Tiles are looked up by `GridPos`, and looking up a position
+/// outside of the map returns `NavmeshOutOfBounds` instead of panicking.
+#[derive(Resource, Clone)]
+pub struct Navmesh(pub(super) Vec<Vec<NavTileOccupant>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NavmeshOutOfBounds(pub GridPos);
+
+impl std::fmt::Display for NavmeshOutOfBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "tile ({}, {}) is outside of the navmesh",
+            self.0.x, self.0.y
+        )
+    }
 }
 
-#[derive(Resource)]
-pub struct Navmesh(pub Vec<Vec<NavTileOccupant>>);
+impl std::error::Error for NavmeshOutOfBounds {}
 
 impl Default for Navmesh {
     fn default() -> Self {
@@ -28,11 +99,148 @@ impl Default for Navmesh {
     }
 }
 
-#[derive(Debug, Event)]
+impl Navmesh {
+    pub fn in_bounds(&self, pos: GridPos) -> bool {
+        pos.x >= 0
+            && pos.y >= 0
+            && (pos.x as usize) < self.0.len()
+            && (pos.y as usize) < self.0[pos.x as usize].len()
+    }
+
+    pub fn get(&self, pos: GridPos) -> Result<&NavTileOccupant, NavmeshOutOfBounds> {
+        if !self.in_bounds(pos) {
+            return Err(NavmeshOutOfBounds(pos));
+        }
+        Ok(&self.0[pos.x as usize][pos.y as usize])
+    }
+
+    pub fn get_mut(&mut self, pos: GridPos) -> Result<&mut NavTileOccupant, NavmeshOutOfBounds> {
+        if !self.in_bounds(pos) {
+            return Err(NavmeshOutOfBounds(pos));
+        }
+        Ok(&mut self.0[pos.x as usize][pos.y as usize])
+    }
+
+    pub fn set_walkable(&mut self, pos: GridPos, walkable: bool) -> Result<(), NavmeshOutOfBounds> {
+        self.get_mut(pos)?.walkable = walkable;
+        Ok(())
+    }
+
+    /// Record `entity` as standing on (or being built on) the tile at `pos`
+    pub fn occupy(&mut self, pos: GridPos, entity: Entity) -> Result<(), NavmeshOutOfBounds> {
+        self.get_mut(pos)?.occupied_by.insert(entity);
+        Ok(())
+    }
+
+    pub fn vacate(&mut self, pos: GridPos, entity: Entity) -> Result<(), NavmeshOutOfBounds> {
+        self.get_mut(pos)?.occupied_by.remove(&entity);
+        Ok(())
+    }
+
+    /// The in-bounds tiles surrounding `pos`, orthogonal neighbours first, followed by the diagonals.
+    /// Walkability is not checked.
+    pub fn neighbors(&self, pos: GridPos) -> impl Iterator<Item = GridPos> + '_ {
+        const OFFSETS: [(i32, i32); 8] = [
+            (0, 1),
+            (0, -1),
+            (-1, 0),
+            (1, 0),
+            (1, 1),
+            (1, -1),
+            (-1, 1),
+            (-1, -1),
+        ];
+
+        OFFSETS
+            .iter()
+            .map(move |&(dx, dy)| GridPos::new(pos.x + dx, pos.y + dy))
+            .filter(|&neighbor| self.in_bounds(neighbor))
+    }
+
+    /// Recompute the clearance of every tile, working down from the top right corner so each tile can
+    /// build on the clearance of the tiles above and to the right of it.
+    pub fn update_clearance(&mut self) {
+        for x in (0..self.0.len()).rev() {
+            for y in (0..self.0[x].len()).rev() {
+                self.0[x][y].clearance = self.clearance_of(x, y);
+            }
+        }
+    }
+
+    /// Recompute the clearance of only the tiles whose clearance can depend on the tiles in `changed`
+    pub fn update_clearance_around(&mut self, changed: impl IntoIterator<Item = GridPos>) {
+        let reach = MAX_AGENT_SIZE as i32;
+        let mut dirty = changed
+            .into_iter()
+            .flat_map(|pos| {
+                (0..reach).flat_map(move |dx| (0..reach).map(move |dy| (pos.x - dx, pos.y - dy)))
+            })
+            .filter(|&(x, y)| self.in_bounds(GridPos::new(x, y)))
+            .map(|(x, y)| (x as usize, y as usize))
+            .collect::<Vec<_>>();
+
+        // same order as `update_clearance`, so the tiles above and to the right are always done first
+        dirty.sort_unstable_by(|a, b| b.cmp(a));
+        dirty.dedup();
+
+        for (x, y) in dirty {
+            self.0[x][y].clearance = self.clearance_of(x, y);
+        }
+    }
+
+    fn clearance_of(&self, x: usize, y: usize) -> u32 {
+        if !self.0[x][y].walkable {
+            return 0;
+        }
+
+        let clearance_at = |x: usize, y: usize| {
+            self.0
+                .get(x)
+                .and_then(|row| row.get(y))
+                .map_or(0, |tile| tile.clearance)
+        };
+
+        (1 + clearance_at(x + 1, y)
+            .min(clearance_at(x, y + 1))
+            .min(clearance_at(x + 1, y + 1)))
+        .min(MAX_AGENT_SIZE)
+    }
+
+    /// Every tile of the navmesh along with its position
+    pub fn tiles(&self) -> impl Iterator<Item = (GridPos, &NavTileOccupant)> {
+        self.0.iter().enumerate().flat_map(|(x, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(y, tile)| (GridPos::new(x as i32, y as i32), tile))
+        })
+    }
+}
+
+/// Sent by `NavmeshMut` whenever a navmesh tile is modified
+#[derive(Debug, Clone, Event)]
+pub struct NavTileChanged {
+    pub pos: GridPos,
+    pub old: NavTileOccupant,
+    pub new: NavTileOccupant,
+}
+
+impl NavTileChanged {
+    /// Whether the change could alter a path through the tile. Only the entities occupying it changing does not.
+    pub fn affects_pathing(&self) -> bool {
+        self.old.walkable != self.new.walkable
+            || self.old.weight != self.new.weight
+            || self.old.passable_by != self.new.passable_by
+    }
+}
+
+#[derive(Debug, Clone, Event)]
 pub struct PathfindRequest {
     pub start: Vec2,
     pub end: Vec2,
     pub entity: Entity,
+    /// The width, in tiles, of the square the entity occupies. The path follows its bottom left tile.
+    pub agent_size: u32,
+    pub faction: Faction,
 }
 
 #[derive(Debug, Event)]
@@ -41,3 +249,35 @@ pub struct PathfindAnswer {
     pub entity: Entity,
     pub target: Vec2,
 }
+
+/// A `PathfindRequest` waiting to be dispatched, tagged with a per-entity request id so that
+/// answers to requests which have since been superseded can be dropped.
+pub struct QueuedPathfindRequest {
+    pub request: PathfindRequest,
+    pub request_id: u64,
+}
+
+pub struct PathfindTask {
+    pub request: QueuedPathfindRequest,
+    /// The navmesh generation the search was run against
+    pub generation: u64,
+    pub task: Task<Option<Vec<Vec2>>>,
+}
+
+/// Pathfinding work which is run on the `AsyncComputeTaskPool` against an immutable navmesh snapshot.
+#[derive(Resource, Default)]
+pub struct PathfindingQueue {
+    pub pending: VecDeque<QueuedPathfindRequest>,
+    pub in_flight: Vec<PathfindTask>,
+    /// The most recent request id for each entity. Anything older is stale.
+    pub latest_request: HashMap<Entity, u64>,
+    pub next_request_id: u64,
+    /// Incremented every time the navmesh changes in a way that could alter a path
+    pub generation: u64,
+    /// Only retaken once a search needs it, so a navmesh which changes every frame isn't copied every frame
+    pub snapshot: Arc<Navmesh>,
+    /// The `NavmeshHierarchy` as it was when `snapshot` was taken, for long searches
+    pub hierarchy_snapshot: Arc<NavmeshHierarchy>,
+    /// The `generation` the snapshots were taken at, `None` until the first search takes them
+    pub snapshot_generation: Option<u64>,
+}
@@ -0,0 +1,228 @@
+use super::components::{Faction, NavmeshSettings};
+use super::utils::{can_step, entry_cost, neighbor_offsets, unscaled_cost, NavAgent, TileBounds};
+use super::Navmesh;
+use crate::utils::GridPos;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+type Tile = (i32, i32);
+
+const UNREACHABLE: u32 = u32::MAX;
+/// Flow fields are only followed by single tile enemies, which can not walk through doors
+const AGENT: NavAgent = NavAgent {
+    size: 1,
+    faction: Faction::Enemy,
+};
+
+/// An integration field holding the cost of the cheapest route from every tile to the nearest goal tile.
+/// Any number of agents can follow it towards the goal by stepping to the cheapest neighbour, without
+/// running a search of their own.
+pub struct FlowField {
+    goals: HashSet<Tile>,
+    costs: Vec<Vec<u32>>,
+    /// The walkability of every tile the last time the field was updated, used to work out which tiles changed
+    walkable: Vec<Vec<bool>>,
+}
+
+impl FlowField {
+    pub fn new(
+        navmesh: &Navmesh,
+        settings: &NavmeshSettings,
+        goals: impl IntoIterator<Item = Vec2>,
+    ) -> Self {
+        let size = TileBounds::of_navmesh(navmesh).max;
+        let mut field = Self {
+            goals: goals
+                .into_iter()
+                .map(|goal| (goal.x as i32, goal.y as i32))
+                .collect(),
+            costs: vec![vec![UNREACHABLE; size.1 as usize]; size.0 as usize],
+            walkable: navmesh
+                .0
+                .iter()
+                .map(|row| row.iter().map(|tile| tile.walkable).collect())
+                .collect(),
+        };
+
+        let mut open = BinaryHeap::new();
+        for &goal in &field.goals {
+            if TileBounds::of_navmesh(navmesh).contains(goal) {
+                field.costs[goal.0 as usize][goal.1 as usize] = 0;
+                open.push(Reverse((0, goal)));
+            }
+        }
+        field.propagate(navmesh, settings, open);
+
+        field
+    }
+
+    /// Bring the field up to date with the tiles in `changed`. Only the tiles whose route ran through a tile
+    /// that became non-walkable, and the tiles that can now reach the goal more cheaply, are recomputed.
+    pub fn update(
+        &mut self,
+        navmesh: &Navmesh,
+        settings: &NavmeshSettings,
+        changed: impl IntoIterator<Item = GridPos>,
+    ) {
+        let mut blocked = Vec::new();
+        let mut opened = Vec::new();
+
+        for pos in changed {
+            let Ok(tile) = navmesh.get(pos) else {
+                continue;
+            };
+            let (x, y) = (pos.x as usize, pos.y as usize);
+            if self.walkable[x][y] == tile.walkable {
+                continue;
+            }
+            self.walkable[x][y] = tile.walkable;
+            if tile.walkable {
+                opened.push((pos.x, pos.y));
+            } else {
+                blocked.push((pos.x, pos.y));
+            }
+        }
+
+        if blocked.is_empty() && opened.is_empty() {
+            return;
+        }
+
+        // Invalidate every tile whose cheapest route could have passed through (or diagonally around) a
+        // blocked tile. Costs can only go up when a tile is blocked, so everything else is still correct.
+        let mut invalidated = HashSet::<Tile>::new();
+        let mut to_check = VecDeque::new();
+        for &tile in &blocked {
+            for seed in std::iter::once(tile).chain(self.neighbors(navmesh, settings, tile)) {
+                if self.cost_of(seed) != UNREACHABLE
+                    && !self.goals.contains(&seed)
+                    && invalidated.insert(seed)
+                {
+                    to_check.push_back(seed);
+                }
+            }
+        }
+
+        while let Some(tile) = to_check.pop_front() {
+            let tile_cost = self.cost_of(tile);
+            for neighbor in self.neighbors(navmesh, settings, tile) {
+                let neighbor_cost = self.cost_of(neighbor);
+                if neighbor_cost == UNREACHABLE
+                    || self.goals.contains(&neighbor)
+                    || invalidated.contains(&neighbor)
+                {
+                    continue;
+                }
+
+                // the neighbour's cheapest route steps onto this tile, so it has to be recomputed too
+                if neighbor_cost == tile_cost + entry_cost(navmesh, neighbor, tile) {
+                    invalidated.insert(neighbor);
+                    to_check.push_back(neighbor);
+                }
+            }
+        }
+
+        for &(x, y) in &invalidated {
+            self.costs[x as usize][y as usize] = UNREACHABLE;
+        }
+
+        // re-seed from the edge of the invalidated region and from around every newly walkable tile
+        let mut open = BinaryHeap::new();
+        for &tile in invalidated.iter().chain(opened.iter()) {
+            for neighbor in self.neighbors(navmesh, settings, tile) {
+                let neighbor_cost = self.cost_of(neighbor);
+                if neighbor_cost != UNREACHABLE {
+                    open.push(Reverse((neighbor_cost, neighbor)));
+                }
+            }
+        }
+
+        self.propagate(navmesh, settings, open);
+    }
+
+    pub fn is_goal(&self, tile: Vec2) -> bool {
+        self.goals.contains(&(tile.x as i32, tile.y as i32))
+    }
+
+    /// The cost of the cheapest route from `tile` to a goal, or `None` if no goal can be reached.
+    pub fn cost(&self, tile: Vec2) -> Option<f32> {
+        let tile = (tile.x as i32, tile.y as i32);
+        if !self.in_bounds(tile) {
+            return None;
+        }
+
+        let cost = self.cost_of(tile);
+        (cost != UNREACHABLE).then(|| unscaled_cost(cost))
+    }
+
+    /// The neighbouring tile an agent standing on `tile` should step to next, or `None` if no goal can be
+    /// reached from here.
+    pub fn next_step(
+        &self,
+        navmesh: &Navmesh,
+        settings: &NavmeshSettings,
+        tile: Vec2,
+    ) -> Option<Vec2> {
+        let tile = (tile.x as i32, tile.y as i32);
+        if !self.in_bounds(tile) {
+            return None;
+        }
+
+        neighbor_offsets(settings)
+            .iter()
+            .map(|&(dx, dy)| (tile.0 + dx, tile.1 + dy))
+            .filter(|&neighbor| {
+                can_step(navmesh, tile, neighbor, AGENT, |t| self.goals.contains(&t))
+            })
+            .filter(|&neighbor| self.cost_of(neighbor) != UNREACHABLE)
+            .min_by_key(|&neighbor| self.cost_of(neighbor) + entry_cost(navmesh, tile, neighbor))
+            .map(|(x, y)| Vec2::new(x as f32, y as f32))
+    }
+
+    /// Dijkstra outwards from the tiles in `open`, lowering the cost of any tile that can step onto them
+    fn propagate(
+        &mut self,
+        navmesh: &Navmesh,
+        settings: &NavmeshSettings,
+        mut open: BinaryHeap<Reverse<(u32, Tile)>>,
+    ) {
+        while let Some(Reverse((cost, tile))) = open.pop() {
+            if cost > self.cost_of(tile) {
+                continue;
+            }
+
+            for neighbor in self.neighbors(navmesh, settings, tile) {
+                if self.goals.contains(&neighbor)
+                    || !navmesh.0[neighbor.0 as usize][neighbor.1 as usize].walkable
+                    || !can_step(navmesh, neighbor, tile, AGENT, |t| self.goals.contains(&t))
+                {
+                    continue;
+                }
+
+                let candidate = cost + entry_cost(navmesh, neighbor, tile);
+                if candidate < self.cost_of(neighbor) {
+                    self.costs[neighbor.0 as usize][neighbor.1 as usize] = candidate;
+                    open.push(Reverse((candidate, neighbor)));
+                }
+            }
+        }
+    }
+
+    /// Every in-bounds tile around `tile`, whether or not it is walkable
+    fn neighbors(&self, navmesh: &Navmesh, settings: &NavmeshSettings, (x, y): Tile) -> Vec<Tile> {
+        navmesh
+            .neighbors(GridPos::new(x, y))
+            .filter(|neighbor| settings.allow_diagonals || neighbor.x == x || neighbor.y == y)
+            .map(|neighbor| (neighbor.x, neighbor.y))
+            .collect()
+    }
+
+    fn in_bounds(&self, (x, y): Tile) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.costs.len() && (y as usize) < self.costs[0].len()
+    }
+
+    fn cost_of(&self, (x, y): Tile) -> u32 {
+        self.costs[x as usize][y as usize]
+    }
+}
@@ -0,0 +1,504 @@
+use super::components::{Faction, NavmeshSettings};
+use super::utils::{
+    find_path_within, heuristic, scaled_cost, successors, unscaled_cost, NavAgent, TileBounds,
+};
+use super::Navmesh;
+use crate::utils::GridPos;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use pathfinding::prelude::*;
+
+/// The width and height, in tiles, of a single navmesh chunk
+pub const CHUNK_SIZE: i32 = 16;
+/// Walkable runs along a chunk border at least this long get an entrance at both ends instead of
+/// a single one in the middle, so that routes hugging either side are not forced through the centre.
+const LONG_ENTRANCE_LENGTH: i32 = 6;
+/// The entrance graph is only built for single tile agents. Wider agents search the full navmesh.
+const AGENT_SIZE: u32 = 1;
+
+type Tile = (i32, i32);
+type Chunk = (i32, i32);
+
+/// A border between two chunks, owned by the chunk on its west or south side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Border {
+    East(Chunk),
+    North(Chunk),
+}
+
+fn chunk_of((x, y): Tile) -> Chunk {
+    (x / CHUNK_SIZE, y / CHUNK_SIZE)
+}
+
+/// Hierarchical pathfinding (HPA*) layer over the `Navmesh`. The grid is split into `CHUNK_SIZE` chunks,
+/// walkable gaps along chunk borders become entrances, and the cost of travelling between the entrances of a
+/// chunk is precomputed. Long distance queries search the small entrance graph instead of every tile, and
+/// only the chunks touched by a navmesh change are recomputed.
+///
+/// Each faction gets its own entrance graph, since doors are open to colonists but not to enemies.
+#[derive(Resource, Clone)]
+pub struct NavmeshHierarchy {
+    colonist: FactionHierarchy,
+    enemy: FactionHierarchy,
+}
+
+impl Default for NavmeshHierarchy {
+    fn default() -> Self {
+        Self {
+            colonist: FactionHierarchy::new(Faction::Colonist),
+            enemy: FactionHierarchy::new(Faction::Enemy),
+        }
+    }
+}
+
+impl NavmeshHierarchy {
+    /// Bring the hierarchy up to date with the navmesh, recomputing only the chunks holding a tile in `changed`
+    /// and the chunks that share a border with them. The first update builds every chunk.
+    pub fn update(&mut self, navmesh: &Navmesh, settings: &NavmeshSettings, changed: &[GridPos]) {
+        self.colonist.update(navmesh, settings, changed);
+        self.enemy.update(navmesh, settings, changed);
+    }
+
+    /// Find a full tile by tile path for a single tile agent of `faction` by searching the entrance graph and
+    /// then refining each leg of the route inside its chunk.
+    pub fn find_path(
+        &self,
+        navmesh: &Navmesh,
+        settings: &NavmeshSettings,
+        start: Vec2,
+        end: Vec2,
+        faction: Faction,
+    ) -> Option<(Vec<Vec2>, f32)> {
+        match faction {
+            Faction::Colonist => &self.colonist,
+            Faction::Enemy => &self.enemy,
+        }
+        .find_path(navmesh, settings, start, end)
+    }
+
+    /// Whether searches for `agent` can go through the hierarchy
+    pub fn supports(agent: NavAgent) -> bool {
+        agent.size == AGENT_SIZE
+    }
+}
+
+#[derive(Clone)]
+struct FactionHierarchy {
+    agent: NavAgent,
+    chunk_count: (i32, i32),
+    /// Whether every tile was walkable for the faction, and its weight, the last time the hierarchy was
+    /// updated. Used to work out which chunks a navmesh change touched.
+    tiles: Vec<Vec<(bool, f32)>>,
+    /// Pairs of walkable tiles on either side of a border
+    entrances: HashMap<Border, Vec<(Tile, Tile)>>,
+    /// Cost of walking between each pair of entrance tiles in a chunk without leaving the chunk
+    intra_edges: HashMap<Chunk, HashMap<Tile, Vec<(Tile, u32)>>>,
+    /// Cost of stepping across a border from one entrance tile to its pair
+    inter_edges: HashMap<Tile, Vec<(Tile, u32)>>,
+}
+
+impl FactionHierarchy {
+    fn new(faction: Faction) -> Self {
+        Self {
+            agent: NavAgent {
+                size: AGENT_SIZE,
+                faction,
+            },
+            chunk_count: (0, 0),
+            tiles: Vec::new(),
+            entrances: HashMap::new(),
+            intra_edges: HashMap::new(),
+            inter_edges: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, navmesh: &Navmesh, settings: &NavmeshSettings, changed: &[GridPos]) {
+        let faction = self.agent.faction;
+        let size = TileBounds::of_navmesh(navmesh).max;
+        let rebuild_all = self.tiles.len() != size.0 as usize;
+
+        let mut dirty_chunks = HashSet::<Chunk>::new();
+        if rebuild_all {
+            self.chunk_count = (
+                (size.0 + CHUNK_SIZE - 1) / CHUNK_SIZE,
+                (size.1 + CHUNK_SIZE - 1) / CHUNK_SIZE,
+            );
+            self.tiles = navmesh
+                .0
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|tile| (tile.is_walkable_for(faction), tile.weight))
+                        .collect()
+                })
+                .collect();
+            self.entrances.clear();
+            self.intra_edges.clear();
+            self.inter_edges.clear();
+            dirty_chunks.extend(
+                (0..self.chunk_count.0).flat_map(|x| (0..self.chunk_count.1).map(move |y| (x, y))),
+            );
+        } else {
+            for &pos in changed {
+                let Ok(tile) = navmesh.get(pos) else {
+                    continue;
+                };
+                let (x, y) = (pos.x as usize, pos.y as usize);
+                let current = (tile.is_walkable_for(faction), tile.weight);
+                if self.tiles[x][y] != current {
+                    self.tiles[x][y] = current;
+                    dirty_chunks.insert(chunk_of((pos.x, pos.y)));
+                }
+            }
+        }
+
+        if dirty_chunks.is_empty() {
+            return;
+        }
+
+        // rebuilding a border changes the entrances of the chunk on the other side too
+        let mut affected_chunks = dirty_chunks.clone();
+        for &(chunk_x, chunk_y) in &dirty_chunks {
+            let borders = [
+                Border::East((chunk_x, chunk_y)),
+                Border::North((chunk_x, chunk_y)),
+                Border::East((chunk_x - 1, chunk_y)),
+                Border::North((chunk_x, chunk_y - 1)),
+            ];
+
+            for border in borders {
+                let Some((low, high)) = self.border_chunks(border) else {
+                    continue;
+                };
+                let entrances = self.find_entrances(border);
+                self.replace_entrances(border, entrances);
+                affected_chunks.insert(low);
+                affected_chunks.insert(high);
+            }
+        }
+
+        for chunk in affected_chunks {
+            self.rebuild_intra_edges(navmesh, settings, chunk);
+        }
+    }
+
+    fn find_path(
+        &self,
+        navmesh: &Navmesh,
+        settings: &NavmeshSettings,
+        start: Vec2,
+        end: Vec2,
+    ) -> Option<(Vec<Vec2>, f32)> {
+        let (waypoints, cost) =
+            self.abstract_path(navmesh, settings, tile_of(start), tile_of(end))?;
+
+        let mut path = vec![waypoints[0]];
+        for leg in waypoints.windows(2) {
+            let (from, to) = (leg[0], leg[1]);
+            if from == to {
+                continue;
+            }
+
+            if chunk_of(from) != chunk_of(to) {
+                // crossing a border between two neighbouring entrance tiles
+                path.push(to);
+                continue;
+            }
+
+            let (leg_path, _) = find_path_within(
+                navmesh,
+                settings,
+                self.chunk_bounds(chunk_of(from)),
+                from,
+                to,
+                self.agent,
+            )?;
+            path.extend(leg_path.into_iter().skip(1));
+        }
+
+        let path = path
+            .into_iter()
+            .map(|(x, y)| Vec2::new(x as f32, y as f32))
+            .collect::<Vec<_>>();
+
+        Some((path, unscaled_cost(cost)))
+    }
+
+    fn abstract_path(
+        &self,
+        navmesh: &Navmesh,
+        settings: &NavmeshSettings,
+        start: Tile,
+        end: Tile,
+    ) -> Option<(Vec<Tile>, u32)> {
+        let navmesh_bounds = TileBounds::of_navmesh(navmesh);
+        if !navmesh_bounds.contains(start) || !navmesh_bounds.contains(end) {
+            return None;
+        }
+
+        let start_chunk = chunk_of(start);
+        let end_chunk = chunk_of(end);
+
+        // short trips that never need to leave their chunk don't need the entrance graph at all
+        if start_chunk == end_chunk {
+            if let Some((_, cost)) = find_path_within(
+                navmesh,
+                settings,
+                self.chunk_bounds(start_chunk),
+                start,
+                end,
+                self.agent,
+            ) {
+                return Some((vec![start, end], cost));
+            }
+        }
+
+        // connect the start and end tiles to the entrances of their chunks
+        let start_bounds = self.chunk_bounds(start_chunk);
+        let reachable_from_start = dijkstra_all(&start, |&tile| {
+            successors(navmesh, settings, start_bounds, tile, self.agent, |_| false)
+        });
+        let start_edges = self
+            .chunk_entrances(start_chunk)
+            .filter_map(|node| {
+                reachable_from_start
+                    .get(&node)
+                    .map(|&(_, cost)| (node, cost))
+            })
+            .collect::<Vec<_>>();
+
+        let end_bounds = self.chunk_bounds(end_chunk);
+        let end_edges = self
+            .chunk_entrances(end_chunk)
+            .filter_map(|node| {
+                find_path_within(navmesh, settings, end_bounds, node, end, self.agent)
+                    .map(|(_, cost)| (node, cost))
+            })
+            .collect::<HashMap<_, _>>();
+
+        if start_edges.is_empty() || end_edges.is_empty() {
+            return None;
+        }
+
+        astar(
+            &start,
+            |&tile| {
+                let mut neighbors = Vec::new();
+                if tile == start {
+                    neighbors.extend(start_edges.iter().copied());
+                }
+                if let Some(edges) = self
+                    .intra_edges
+                    .get(&chunk_of(tile))
+                    .and_then(|chunk| chunk.get(&tile))
+                {
+                    neighbors.extend(edges.iter().copied());
+                }
+                if let Some(edges) = self.inter_edges.get(&tile) {
+                    neighbors.extend(edges.iter().copied());
+                }
+                if let Some(&cost) = end_edges.get(&tile) {
+                    neighbors.push((end, cost));
+                }
+                neighbors
+            },
+            |&tile| heuristic(settings, tile, end),
+            |&tile| tile == end,
+        )
+    }
+
+    fn chunk_bounds(&self, (chunk_x, chunk_y): Chunk) -> TileBounds {
+        let size = (
+            self.tiles.len() as i32,
+            self.tiles.first().map(Vec::len).unwrap_or(0) as i32,
+        );
+        TileBounds {
+            min: (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE),
+            max: (
+                ((chunk_x + 1) * CHUNK_SIZE).min(size.0),
+                ((chunk_y + 1) * CHUNK_SIZE).min(size.1),
+            ),
+        }
+    }
+
+    /// The chunks on the low and high side of a border, or `None` if the border is on the edge of the map.
+    fn border_chunks(&self, border: Border) -> Option<(Chunk, Chunk)> {
+        let (low, high) = match border {
+            Border::East((x, y)) => ((x, y), (x + 1, y)),
+            Border::North((x, y)) => ((x, y), (x, y + 1)),
+        };
+        let in_range =
+            |(x, y): Chunk| x >= 0 && y >= 0 && x < self.chunk_count.0 && y < self.chunk_count.1;
+
+        (in_range(low) && in_range(high)).then_some((low, high))
+    }
+
+    fn find_entrances(&self, border: Border) -> Vec<(Tile, Tile)> {
+        let Some((low, _)) = self.border_chunks(border) else {
+            return Vec::new();
+        };
+        let bounds = self.chunk_bounds(low);
+
+        // the pair of tiles either side of the border at `offset` along it
+        let pair_at = |offset: i32| -> (Tile, Tile) {
+            match border {
+                Border::East(_) => ((bounds.max.0 - 1, offset), (bounds.max.0, offset)),
+                Border::North(_) => ((offset, bounds.max.1 - 1), (offset, bounds.max.1)),
+            }
+        };
+        let range = match border {
+            Border::East(_) => bounds.min.1..bounds.max.1,
+            Border::North(_) => bounds.min.0..bounds.max.0,
+        };
+        let walkable = |(x, y): Tile| self.tiles[x as usize][y as usize].0;
+
+        let mut entrances = Vec::new();
+        let mut run_start = None;
+        for offset in range.clone().chain(std::iter::once(range.end)) {
+            let open = offset < range.end && {
+                let (a, b) = pair_at(offset);
+                walkable(a) && walkable(b)
+            };
+
+            match (open, run_start) {
+                (true, None) => run_start = Some(offset),
+                (false, Some(first)) => {
+                    let last = offset - 1;
+                    if last - first + 1 >= LONG_ENTRANCE_LENGTH {
+                        entrances.push(pair_at(first));
+                        entrances.push(pair_at(last));
+                    } else {
+                        entrances.push(pair_at((first + last) / 2));
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+
+        entrances
+    }
+
+    fn chunk_entrances(&self, chunk: Chunk) -> impl Iterator<Item = Tile> + '_ {
+        self.intra_edges
+            .get(&chunk)
+            .into_iter()
+            .flat_map(|nodes| nodes.keys().copied())
+    }
+
+    /// Swap the entrances along `border` for `entrances`, along with the edges crossing the border between them
+    fn replace_entrances(&mut self, border: Border, entrances: Vec<(Tile, Tile)>) {
+        for (a, b) in self.entrances.remove(&border).into_iter().flatten() {
+            for (from, to) in [(a, b), (b, a)] {
+                let Some(edges) = self.inter_edges.get_mut(&from) else {
+                    continue;
+                };
+                edges.retain(|&(other, _)| other != to);
+                if edges.is_empty() {
+                    self.inter_edges.remove(&from);
+                }
+            }
+        }
+
+        for &(a, b) in &entrances {
+            let weight = |(x, y): Tile| self.tiles[x as usize][y as usize].1;
+            let a_to_b = scaled_cost(weight(b));
+            let b_to_a = scaled_cost(weight(a));
+
+            self.inter_edges.entry(a).or_default().push((b, a_to_b));
+            self.inter_edges.entry(b).or_default().push((a, b_to_a));
+        }
+        self.entrances.insert(border, entrances);
+    }
+
+    fn rebuild_intra_edges(&mut self, navmesh: &Navmesh, settings: &NavmeshSettings, chunk: Chunk) {
+        let bounds = self.chunk_bounds(chunk);
+
+        let nodes = self
+            .entrances
+            .values()
+            .flatten()
+            .flat_map(|&(a, b)| [a, b])
+            .filter(|&tile| chunk_of(tile) == chunk)
+            .collect::<HashSet<_>>();
+
+        let edges = nodes
+            .iter()
+            .map(|&node| {
+                let reachable = dijkstra_all(&node, |&tile| {
+                    successors(navmesh, settings, bounds, tile, self.agent, |_| false)
+                });
+                let node_edges = nodes
+                    .iter()
+                    .filter_map(|other| reachable.get(other).map(|&(_, cost)| (*other, cost)))
+                    .collect::<Vec<_>>();
+                (node, node_edges)
+            })
+            .collect::<HashMap<_, _>>();
+
+        self.intra_edges.insert(chunk, edges);
+    }
+}
+
+fn tile_of(position: Vec2) -> Tile {
+    (position.x as i32, position.y as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navmesh::components::NavTileOccupant;
+    use crate::navmesh::utils::find_path;
+
+    /// A walkable corridor spanning two chunks, three tiles tall, so the chunks share a single entrance
+    fn corridor() -> Navmesh {
+        let tile = NavTileOccupant {
+            walkable: true,
+            weight: 1.,
+            ..default()
+        };
+        Navmesh(vec![vec![tile; 3]; CHUNK_SIZE as usize * 2])
+    }
+
+    fn assert_matches_astar(hierarchy: &NavmeshHierarchy, navmesh: &Navmesh) -> f32 {
+        let settings = NavmeshSettings::default();
+        let (start, end) = (
+            Vec2::new(1., 1.),
+            Vec2::new(CHUNK_SIZE as f32 * 2. - 2., 1.),
+        );
+        let agent = NavAgent {
+            size: AGENT_SIZE,
+            faction: Faction::Colonist,
+        };
+
+        let (_, expected) = find_path(navmesh, &settings, start, end, agent).unwrap();
+        let (path, cost) = hierarchy
+            .find_path(navmesh, &settings, start, end, Faction::Colonist)
+            .unwrap();
+
+        assert_eq!(cost, expected);
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        cost
+    }
+
+    #[test]
+    fn find_path_cost_matches_astar_after_wall_placed_and_removed() {
+        let settings = NavmeshSettings::default();
+        let mut navmesh = corridor();
+        let mut hierarchy = NavmeshHierarchy::default();
+        hierarchy.update(&navmesh, &settings, &[]);
+        let open_cost = assert_matches_astar(&hierarchy, &navmesh);
+
+        // wall off the middle of the chunk border, which splits its entrance in two
+        let wall = GridPos::new(CHUNK_SIZE - 1, 1);
+        navmesh.set_walkable(wall, false).unwrap();
+        hierarchy.update(&navmesh, &settings, &[wall]);
+        let walled_cost = assert_matches_astar(&hierarchy, &navmesh);
+        assert!(walled_cost > open_cost);
+
+        navmesh.set_walkable(wall, true).unwrap();
+        hierarchy.update(&navmesh, &settings, &[wall]);
+        assert_eq!(assert_matches_astar(&hierarchy, &navmesh), open_cost);
+    }
+}
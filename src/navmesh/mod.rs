@@ -1,14 +1,32 @@
 pub mod components;
+mod flow_field;
+mod hierarchy;
+mod mutation;
+mod path_cache;
+mod regions;
+mod siege;
 pub mod systems;
 mod utils;
 
-use self::components::{PathfindAnswer, PathfindRequest, ToggleNavmeshDebug};
+use self::components::{
+    NavTileChanged, NavmeshSettings, PathfindAnswer, PathfindRequest, PathfindingQueue,
+    ToggleNavmeshDebug,
+};
+use self::path_cache::PathCache;
+use crate::GameState;
 use bevy::prelude::*;
 pub use components::Navmesh;
+pub use flow_field::FlowField;
+pub use hierarchy::NavmeshHierarchy;
+pub use mutation::NavmeshMut;
+pub use regions::NavmeshRegions;
 
 pub mod prelude {
     pub use super::components::*;
-    pub use super::utils::get_pathing;
+    pub use super::mutation::NavmeshMut;
+    pub use super::regions::NavmeshRegions;
+    pub use super::siege::find_siege_path;
+    pub use super::utils::{find_path_in_range, segment_tiles, NavAgent};
 }
 
 #[derive(SystemSet, Hash, Debug, Clone, Eq, PartialEq)]
@@ -23,7 +41,18 @@ pub struct NavmeshPlugin;
 impl Plugin for NavmeshPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Navmesh>()
+            .init_resource::<NavmeshSettings>()
             .init_resource::<ToggleNavmeshDebug>()
+            .init_resource::<PathfindingQueue>()
+            .init_resource::<NavmeshHierarchy>()
+            .init_resource::<NavmeshRegions>()
+            .init_resource::<PathCache>()
+            .add_systems(Startup, systems::spawn_path_cache_debug_text)
+            .add_systems(OnExit(GameState::GameOver), systems::reset_navmesh)
+            .add_systems(
+                OnEnter(GameState::FactoryPlacement),
+                systems::build_navmesh_clearance,
+            )
             .configure_sets(
                 Update,
                 (
@@ -33,14 +62,39 @@ impl Plugin for NavmeshPlugin {
                 )
                     .chain(),
             )
+            .add_systems(
+                Update,
+                (
+                    systems::update_navmesh_clearance,
+                    (
+                        systems::advance_navmesh_generation,
+                        systems::update_navmesh_hierarchy,
+                        systems::update_navmesh_regions,
+                    ),
+                )
+                    .chain()
+                    // the world is generated by writing to the navmesh directly, without any `NavTileChanged`
+                    // events, so nothing is built from it until the world exists
+                    .run_if(not(in_state(GameState::Loading)))
+                    .run_if(resource_changed::<Navmesh>())
+                    .in_set(NavmeshSystemSet::First),
+            )
             .add_systems(
                 Update,
                 (
                     systems::debug_navmesh,
-                    systems::listen_for_pathfinding_requests,
+                    systems::debug_path_cache,
+                    (
+                        systems::invalidate_path_cache,
+                        systems::collect_pathfinding_results,
+                        systems::listen_for_pathfinding_requests,
+                        systems::dispatch_pathfinding_tasks,
+                    )
+                        .chain(),
                 )
                     .in_set(NavmeshSystemSet::Update),
             )
+            .add_event::<NavTileChanged>()
             .add_event::<PathfindRequest>()
             .add_event::<PathfindAnswer>();
     }
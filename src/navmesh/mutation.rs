@@ -0,0 +1,63 @@
+use super::components::{NavTileChanged, NavTileOccupant, Navmesh, NavmeshOutOfBounds};
+use crate::utils::GridPos;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Mutable access to the `Navmesh` which sends a `NavTileChanged` event for every tile that is modified,
+/// so that consumers can react to the tiles that changed instead of rescanning the whole navmesh.
+#[derive(SystemParam)]
+pub struct NavmeshMut<'w> {
+    navmesh: ResMut<'w, Navmesh>,
+    changes: EventWriter<'w, NavTileChanged>,
+}
+
+impl std::ops::Deref for NavmeshMut<'_> {
+    type Target = Navmesh;
+
+    fn deref(&self) -> &Self::Target {
+        &self.navmesh
+    }
+}
+
+impl NavmeshMut<'_> {
+    /// Apply `update` to the tile at `pos`. No event is sent if `update` leaves the tile unchanged.
+    pub fn update_tile(
+        &mut self,
+        pos: GridPos,
+        update: impl FnOnce(&mut NavTileOccupant),
+    ) -> Result<(), NavmeshOutOfBounds> {
+        self.modify(pos, |navmesh| navmesh.get_mut(pos).map(update))
+    }
+
+    pub fn set_walkable(&mut self, pos: GridPos, walkable: bool) -> Result<(), NavmeshOutOfBounds> {
+        self.modify(pos, |navmesh| navmesh.set_walkable(pos, walkable))
+    }
+
+    pub fn occupy(&mut self, pos: GridPos, entity: Entity) -> Result<(), NavmeshOutOfBounds> {
+        self.modify(pos, |navmesh| navmesh.occupy(pos, entity))
+    }
+
+    pub fn vacate(&mut self, pos: GridPos, entity: Entity) -> Result<(), NavmeshOutOfBounds> {
+        self.modify(pos, |navmesh| navmesh.vacate(pos, entity))
+    }
+
+    /// Run `modify` against the navmesh, then compare the tile at `pos` against its old state. The navmesh is
+    /// only marked as changed (and the event sent) when the tile is actually different.
+    fn modify(
+        &mut self,
+        pos: GridPos,
+        modify: impl FnOnce(&mut Navmesh) -> Result<(), NavmeshOutOfBounds>,
+    ) -> Result<(), NavmeshOutOfBounds> {
+        let old = self.navmesh.get(pos)?.clone();
+        modify(self.navmesh.bypass_change_detection())?;
+        let new = self.navmesh.get(pos)?.clone();
+
+        if new == old {
+            return Ok(());
+        }
+
+        self.navmesh.set_changed();
+        self.changes.send(NavTileChanged { pos, old, new });
+        Ok(())
+    }
+}
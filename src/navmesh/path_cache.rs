@@ -0,0 +1,158 @@
+use super::utils::{segment_clear, segment_tiles, NavAgent};
+use super::{Navmesh, NavmeshRegions};
+use crate::utils::GridPos;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+/// The most paths kept at once. The least recently used path is dropped to make room for a new one.
+const MAX_CACHED_PATHS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PathCacheKey {
+    start_region: u32,
+    goal: GridPos,
+    agent: NavAgent,
+}
+
+struct CachedPath {
+    path: Vec<Vec2>,
+    /// Every tile the path sweeps through. The path is dropped as soon as one of them changes.
+    route: HashSet<(i32, i32)>,
+    last_used: u64,
+}
+
+/// Paths which have already been found, keyed by the region they start in and the tile they lead to. Pawns
+/// walk the same routes over and over (between the stone they are mining and the factory, for example), so
+/// most requests can be answered by joining a path found earlier instead of running a new search.
+#[derive(Resource, Default)]
+pub struct PathCache {
+    paths: HashMap<PathCacheKey, CachedPath>,
+    tick: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl PathCache {
+    /// Look up a path from `start` to `end`. The agent joins the cached path at whichever waypoint it can walk
+    /// straight to that leaves it the shortest trip. If it can not walk straight to any waypoint, it is a miss.
+    pub fn get(
+        &mut self,
+        navmesh: &Navmesh,
+        regions: &NavmeshRegions,
+        start: Vec2,
+        end: Vec2,
+        agent: NavAgent,
+    ) -> Option<Vec<Vec2>> {
+        let path = self.join(navmesh, regions, start, end, agent);
+        if path.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        path
+    }
+
+    pub fn insert(
+        &mut self,
+        regions: &NavmeshRegions,
+        start: Vec2,
+        end: Vec2,
+        agent: NavAgent,
+        path: &[Vec2],
+    ) {
+        // a single waypoint is not worth joining
+        if path.len() < 2 {
+            return;
+        }
+        let Some(key) = cache_key(regions, start, end, agent) else {
+            return;
+        };
+
+        if !self.paths.contains_key(&key) && self.paths.len() >= MAX_CACHED_PATHS {
+            let least_recently_used = self
+                .paths
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(&key, _)| key);
+            if let Some(least_recently_used) = least_recently_used {
+                self.paths.remove(&least_recently_used);
+            }
+        }
+
+        let route = path
+            .windows(2)
+            .flat_map(|segment| segment_tiles(segment[0], segment[1], agent.size))
+            .collect();
+
+        self.tick += 1;
+        self.paths.insert(
+            key,
+            CachedPath {
+                path: path.to_vec(),
+                route,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    /// Drop every path which sweeps through one of the `changed` tiles
+    pub fn invalidate(&mut self, changed: &HashSet<(i32, i32)>) {
+        self.paths
+            .retain(|_, cached| cached.route.is_disjoint(changed));
+    }
+
+    pub fn cached_paths(&self) -> usize {
+        self.paths.len()
+    }
+
+    fn join(
+        &mut self,
+        navmesh: &Navmesh,
+        regions: &NavmeshRegions,
+        start: Vec2,
+        end: Vec2,
+        agent: NavAgent,
+    ) -> Option<Vec<Vec2>> {
+        let key = cache_key(regions, start, end, agent)?;
+        let cached = self.paths.get_mut(&key)?;
+        let path = &cached.path;
+
+        // the length of the rest of the path from each waypoint
+        let mut remaining = vec![0.; path.len()];
+        for index in (0..path.len() - 1).rev() {
+            remaining[index] = remaining[index + 1] + path[index].distance(path[index + 1]);
+        }
+
+        let (join, _) = path
+            .iter()
+            .enumerate()
+            .filter(|&(_, &waypoint)| segment_clear(navmesh, start, waypoint, agent))
+            .map(|(index, &waypoint)| (index, start.distance(waypoint) + remaining[index]))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+        self.tick += 1;
+        cached.last_used = self.tick;
+
+        let mut joined = vec![start];
+        joined.extend(
+            path[join..]
+                .iter()
+                .copied()
+                .skip_while(|&waypoint| waypoint == start),
+        );
+        Some(joined)
+    }
+}
+
+fn cache_key(
+    regions: &NavmeshRegions,
+    start: Vec2,
+    end: Vec2,
+    agent: NavAgent,
+) -> Option<PathCacheKey> {
+    Some(PathCacheKey {
+        start_region: regions.region_of(agent.faction, start)?,
+        goal: GridPos::from_tile_pos_vec(end),
+        agent,
+    })
+}
@@ -0,0 +1,370 @@
+use super::components::Faction;
+use super::Navmesh;
+use crate::utils::GridPos;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use std::collections::VecDeque;
+
+type Tile = (i32, i32);
+
+const ORTHOGONAL_OFFSETS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+/// The eight tiles around a tile in clockwise order, starting above it
+const RING_OFFSETS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+/// Connected-component labels for every walkable tile of the `Navmesh`. Two tiles with the same label can
+/// always reach each other, so reachability checks are a lookup instead of a search. Labels are kept up to
+/// date incrementally as tiles become walkable or non-walkable.
+///
+/// Each faction gets its own labels, since doors connect regions for colonists but not for enemies.
+/// Orthogonal connectivity is enough even when diagonal movement is enabled, since a diagonal step is only
+/// allowed when both corner tiles are walkable.
+#[derive(Resource)]
+pub struct NavmeshRegions {
+    colonist: FactionRegions,
+    enemy: FactionRegions,
+}
+
+impl Default for NavmeshRegions {
+    fn default() -> Self {
+        Self {
+            colonist: FactionRegions::new(Faction::Colonist),
+            enemy: FactionRegions::new(Faction::Enemy),
+        }
+    }
+}
+
+impl NavmeshRegions {
+    /// Bring the labels up to date with the tiles in `changed`, relabelling only the regions they touch.
+    /// The first update labels the whole navmesh.
+    pub fn update(&mut self, navmesh: &Navmesh, changed: &[GridPos]) {
+        self.colonist.update(navmesh, changed);
+        self.enemy.update(navmesh, changed);
+    }
+
+    /// Whether `faction` can walk between two tiles. Either tile may be non-walkable (a stone, a placeable
+    /// or the factory), in which case it is reachable from any region touching it.
+    pub fn can_reach(&self, faction: Faction, start: Vec2, end: Vec2) -> bool {
+        self.for_faction(faction).can_reach(start, end)
+    }
+
+    /// Whether `faction` can walk from `start` to any of `ends`
+    pub fn can_reach_any(
+        &self,
+        faction: Faction,
+        start: Vec2,
+        ends: impl IntoIterator<Item = Vec2>,
+    ) -> bool {
+        let regions = self.for_faction(faction);
+        ends.into_iter().any(|end| regions.can_reach(start, end))
+    }
+
+    /// The region `faction` is standing in at `position`, if the tile is walkable for it
+    pub fn region_of(&self, faction: Faction, position: Vec2) -> Option<u32> {
+        self.for_faction(faction).label(tile_of(position))
+    }
+
+    fn for_faction(&self, faction: Faction) -> &FactionRegions {
+        match faction {
+            Faction::Colonist => &self.colonist,
+            Faction::Enemy => &self.enemy,
+        }
+    }
+}
+
+struct FactionRegions {
+    faction: Faction,
+    labels: Vec<Vec<Option<u32>>>,
+    sizes: HashMap<u32, usize>,
+    next_label: u32,
+}
+
+impl FactionRegions {
+    fn new(faction: Faction) -> Self {
+        Self {
+            faction,
+            labels: Vec::new(),
+            sizes: HashMap::new(),
+            next_label: 0,
+        }
+    }
+
+    fn update(&mut self, navmesh: &Navmesh, changed: &[GridPos]) {
+        if self.labels.len() != navmesh.0.len() {
+            self.rebuild(navmesh);
+            return;
+        }
+
+        for &pos in changed {
+            let Ok(tile) = navmesh.get(pos) else {
+                continue;
+            };
+            let labelled = self.label((pos.x, pos.y)).is_some();
+            let walkable = tile.is_walkable_for(self.faction);
+            if walkable && !labelled {
+                self.open_tile(navmesh, (pos.x, pos.y));
+            } else if !walkable && labelled {
+                self.close_tile(navmesh, (pos.x, pos.y));
+            }
+        }
+    }
+
+    fn can_reach(&self, start: Vec2, end: Vec2) -> bool {
+        let start_regions = self.regions_touching(tile_of(start));
+        if start_regions.is_empty() {
+            return false;
+        }
+
+        self.regions_touching(tile_of(end))
+            .iter()
+            .any(|region| start_regions.contains(region))
+    }
+
+    fn regions_touching(&self, tile: Tile) -> HashSet<u32> {
+        if let Some(label) = self.label(tile) {
+            return HashSet::from_iter([label]);
+        }
+
+        ORTHOGONAL_OFFSETS
+            .iter()
+            .filter_map(|&(dx, dy)| self.label((tile.0 + dx, tile.1 + dy)))
+            .collect()
+    }
+
+    fn label(&self, (x, y): Tile) -> Option<u32> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        *self.labels.get(x as usize)?.get(y as usize)?
+    }
+
+    fn set_label(&mut self, (x, y): Tile, label: Option<u32>) {
+        if let Some(old) = self.labels[x as usize][y as usize] {
+            if let Some(size) = self.sizes.get_mut(&old) {
+                *size -= 1;
+                if *size == 0 {
+                    self.sizes.remove(&old);
+                }
+            }
+        }
+        if let Some(new) = label {
+            *self.sizes.entry(new).or_default() += 1;
+        }
+        self.labels[x as usize][y as usize] = label;
+    }
+
+    fn new_label(&mut self) -> u32 {
+        self.next_label += 1;
+        self.next_label
+    }
+
+    fn rebuild(&mut self, navmesh: &Navmesh) {
+        self.labels = navmesh.0.iter().map(|row| vec![None; row.len()]).collect();
+        self.sizes.clear();
+
+        for x in 0..navmesh.0.len() {
+            for y in 0..navmesh.0[x].len() {
+                let tile = (x as i32, y as i32);
+                if navmesh.0[x][y].is_walkable_for(self.faction) && self.label(tile).is_none() {
+                    let label = self.new_label();
+                    self.flood(navmesh, tile, label);
+                }
+            }
+        }
+    }
+
+    /// Label every walkable tile connected to `start` with `label`
+    fn flood(&mut self, navmesh: &Navmesh, start: Tile, label: u32) {
+        let mut to_visit = VecDeque::from([start]);
+        self.set_label(start, Some(label));
+
+        while let Some((x, y)) = to_visit.pop_front() {
+            for (dx, dy) in ORTHOGONAL_OFFSETS {
+                let neighbor = (x + dx, y + dy);
+                if !is_walkable(navmesh, neighbor, self.faction)
+                    || self.label(neighbor) == Some(label)
+                {
+                    continue;
+                }
+                self.set_label(neighbor, Some(label));
+                to_visit.push_back(neighbor);
+            }
+        }
+    }
+
+    /// A tile became walkable. It joins the region of its neighbours, merging them if it connects several.
+    fn open_tile(&mut self, navmesh: &Navmesh, tile: Tile) {
+        let mut neighbor_regions = ORTHOGONAL_OFFSETS
+            .iter()
+            .filter_map(|&(dx, dy)| self.label((tile.0 + dx, tile.1 + dy)))
+            .collect::<Vec<_>>();
+        neighbor_regions.sort_unstable();
+        neighbor_regions.dedup();
+
+        // keep the largest region's label so the fewest tiles have to be relabelled
+        let Some(&largest) = neighbor_regions
+            .iter()
+            .max_by_key(|region| self.sizes.get(*region).copied().unwrap_or(0))
+        else {
+            let label = self.new_label();
+            self.set_label(tile, Some(label));
+            return;
+        };
+
+        self.set_label(tile, Some(largest));
+        if neighbor_regions.len() > 1 {
+            self.flood(navmesh, tile, largest);
+        }
+    }
+
+    /// A tile became non-walkable. Its region might have been split in two, in which case every piece but
+    /// the first gets a new label.
+    fn close_tile(&mut self, navmesh: &Navmesh, tile: Tile) {
+        self.set_label(tile, None);
+
+        if !self.may_split(navmesh, tile) {
+            return;
+        }
+
+        let neighbors = ORTHOGONAL_OFFSETS
+            .iter()
+            .map(|&(dx, dy)| (tile.0 + dx, tile.1 + dy))
+            .filter(|&neighbor| is_walkable(navmesh, neighbor, self.faction))
+            .collect::<Vec<_>>();
+
+        let mut relabelled = HashSet::<Tile>::new();
+        for (index, &neighbor) in neighbors.iter().enumerate() {
+            if index == 0 || relabelled.contains(&neighbor) {
+                continue;
+            }
+
+            let label = self.new_label();
+            self.flood(navmesh, neighbor, label);
+            relabelled.extend(
+                neighbors
+                    .iter()
+                    .copied()
+                    .filter(|&other| self.label(other) == Some(label)),
+            );
+
+            // everything flooded back into the first neighbour's piece, so there was no split after all
+            if relabelled.contains(&neighbors[0]) {
+                return;
+            }
+        }
+    }
+
+    /// Closing a tile can only split its region if its walkable orthogonal neighbours are not already
+    /// connected to each other by walking around the ring of tiles surrounding it.
+    fn may_split(&self, navmesh: &Navmesh, (x, y): Tile) -> bool {
+        let ring = RING_OFFSETS
+            .iter()
+            .map(|&(dx, dy)| is_walkable(navmesh, (x + dx, y + dy), self.faction))
+            .collect::<Vec<_>>();
+
+        // count the separate runs of walkable tiles around the ring that contain an orthogonal neighbour
+        let runs_with_neighbor = (0..ring.len())
+            .filter(|&index| ring[index] && !ring[(index + ring.len() - 1) % ring.len()])
+            .filter(|&run_start| {
+                (0..ring.len())
+                    .map(|offset| (run_start + offset) % ring.len())
+                    .take_while(|&index| ring[index])
+                    .any(|index| index % 2 == 0)
+            })
+            .count();
+
+        // a fully walkable ring has no run start but is still a single connected piece
+        runs_with_neighbor > 1
+    }
+}
+
+fn is_walkable(navmesh: &Navmesh, (x, y): Tile, faction: Faction) -> bool {
+    x >= 0
+        && y >= 0
+        && navmesh
+            .0
+            .get(x as usize)
+            .and_then(|row| row.get(y as usize))
+            .map(|tile| tile.is_walkable_for(faction))
+            .unwrap_or(false)
+}
+
+fn tile_of(position: Vec2) -> Tile {
+    (position.x as i32, position.y as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navmesh::components::NavTileOccupant;
+
+    const DOORWAY: GridPos = GridPos { x: 3, y: 1 };
+
+    /// Two 3x3 rooms either side of a wall, joined by a single corridor tile through the middle of it
+    fn two_rooms() -> Navmesh {
+        let tiles = (0..7)
+            .map(|x| {
+                (0..3)
+                    .map(|y| NavTileOccupant {
+                        walkable: x != DOORWAY.x || y == DOORWAY.y,
+                        weight: 1.,
+                        ..default()
+                    })
+                    .collect()
+            })
+            .collect();
+        Navmesh(tiles)
+    }
+
+    #[test]
+    fn closing_and_reopening_corridor_splits_and_merges_regions() {
+        let (left, right) = (Vec2::new(0., 0.), Vec2::new(6., 2.));
+        let mut navmesh = two_rooms();
+        let mut regions = NavmeshRegions::default();
+        regions.update(&navmesh, &[]);
+
+        let region = regions.region_of(Faction::Colonist, left);
+        assert!(region.is_some());
+        assert_eq!(regions.region_of(Faction::Colonist, right), region);
+        assert_eq!(
+            regions.region_of(Faction::Colonist, DOORWAY.to_vec2()),
+            region
+        );
+
+        navmesh.set_walkable(DOORWAY, false).unwrap();
+        regions.update(&navmesh, &[DOORWAY]);
+
+        assert_eq!(
+            regions.region_of(Faction::Colonist, DOORWAY.to_vec2()),
+            None
+        );
+        let left_region = regions.region_of(Faction::Colonist, left);
+        let right_region = regions.region_of(Faction::Colonist, right);
+        assert!(left_region.is_some() && right_region.is_some());
+        assert_ne!(left_region, right_region);
+        assert!(!regions.can_reach(Faction::Colonist, left, right));
+        // the closed corridor tile itself still touches both rooms
+        assert!(regions.can_reach(Faction::Colonist, left, DOORWAY.to_vec2()));
+        assert!(regions.can_reach(Faction::Colonist, right, DOORWAY.to_vec2()));
+
+        navmesh.set_walkable(DOORWAY, true).unwrap();
+        regions.update(&navmesh, &[DOORWAY]);
+
+        let region = regions.region_of(Faction::Colonist, left);
+        assert!(region.is_some());
+        assert_eq!(regions.region_of(Faction::Colonist, right), region);
+        assert_eq!(
+            regions.region_of(Faction::Colonist, DOORWAY.to_vec2()),
+            region
+        );
+        assert!(regions.can_reach(Faction::Colonist, left, right));
+    }
+}
@@ -0,0 +1,65 @@
+use super::components::{Faction, NavmeshSettings};
+use super::utils::{heuristic, scaled_cost, successors, NavAgent, TileBounds};
+use super::Navmesh;
+use crate::utils::GridPos;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use pathfinding::prelude::*;
+
+/// Breaking through a structure is worth a detour of up to this many tiles
+const STRUCTURE_COST: f32 = 40.;
+/// Only single tile enemies besiege the colony
+const AGENT: NavAgent = NavAgent {
+    size: 1,
+    faction: Faction::Enemy,
+};
+
+/// A route to the nearest of `goals` for an enemy with no way around the colony's defences. Tiles that
+/// `breakable` accepts are passable at a high cost, so the route only goes through structures where it has
+/// to and then through as few of them as it can.
+pub fn find_siege_path(
+    navmesh: &Navmesh,
+    settings: &NavmeshSettings,
+    start: Vec2,
+    goals: impl IntoIterator<Item = Vec2>,
+    breakable: impl Fn(GridPos) -> bool,
+) -> Option<Vec<Vec2>> {
+    let goals = goals
+        .into_iter()
+        .map(|goal| (goal.x as i32, goal.y as i32))
+        .collect::<HashSet<_>>();
+    let bounds = TileBounds::of_navmesh(navmesh);
+    let is_breakable = |(x, y): (i32, i32)| breakable(GridPos::new(x, y));
+
+    let (path, _) = astar(
+        &(start.x as i32, start.y as i32),
+        |&tile| {
+            successors(navmesh, settings, bounds, tile, AGENT, |t| {
+                goals.contains(&t) || is_breakable(t)
+            })
+            .into_iter()
+            .map(|(next, cost)| {
+                if is_breakable(next) {
+                    (next, cost + scaled_cost(STRUCTURE_COST))
+                } else {
+                    (next, cost)
+                }
+            })
+            .collect::<Vec<_>>()
+        },
+        |&tile| {
+            goals
+                .iter()
+                .map(|&goal| heuristic(settings, tile, goal))
+                .min()
+                .unwrap_or(0)
+        },
+        |tile| goals.contains(tile),
+    )?;
+
+    Some(
+        path.into_iter()
+            .map(|(x, y)| Vec2::new(x as f32, y as f32))
+            .collect(),
+    )
+}
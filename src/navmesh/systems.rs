@@ -1,9 +1,16 @@
 use super::components::*;
+use super::hierarchy::{NavmeshHierarchy, CHUNK_SIZE};
+use super::path_cache::PathCache;
+use super::regions::NavmeshRegions;
+use super::utils::{find_path, smooth_path, NavAgent};
 use crate::utils::*;
 use crate::TILE_SIZE;
+use bevy::ecs::entity::Entities;
 use bevy::prelude::*;
+use bevy::tasks::{block_on, AsyncComputeTaskPool};
+use bevy::utils::HashSet;
 use leafwing_input_manager::prelude::*;
-use pathfinding::prelude::*;
+use std::sync::Arc;
 
 pub fn debug_navmesh(
     navmesh: Res<Navmesh>,
@@ -25,95 +32,322 @@ pub fn debug_navmesh(
 
     let max_weight = 2.;
 
-    for (x, row) in navmesh.0.iter().enumerate() {
-        for (y, tile) in row.iter().enumerate() {
-            let tile_position = Vec2::new(x as f32, y as f32).tile_pos_to_world()
-                + Vec2::new(TILE_SIZE / 2., TILE_SIZE / 2.);
-
-            if !tile.walkable {
-                gizmos.rect_2d(
-                    tile_position,
-                    0.,
-                    Vec2::new(TILE_SIZE, TILE_SIZE),
-                    Color::RED,
-                );
-            } else {
-                let weight_color = Color::rgb(
-                    tile.weight / max_weight,
-                    tile.weight / max_weight,
-                    tile.weight / max_weight,
-                );
-                gizmos.rect_2d(
-                    tile_position,
-                    0.,
-                    Vec2::new(TILE_SIZE, TILE_SIZE),
-                    weight_color,
-                );
-            }
+    for (pos, tile) in navmesh.tiles() {
+        let tile_position =
+            pos.to_vec2().tile_pos_to_world() + Vec2::new(TILE_SIZE / 2., TILE_SIZE / 2.);
+
+        if !tile.walkable {
+            gizmos.rect_2d(
+                tile_position,
+                0.,
+                Vec2::new(TILE_SIZE, TILE_SIZE),
+                Color::RED,
+            );
+        } else {
+            let weight_color = Color::rgb(
+                tile.weight / max_weight,
+                tile.weight / max_weight,
+                tile.weight / max_weight,
+            );
+            gizmos.rect_2d(
+                tile_position,
+                0.,
+                Vec2::new(TILE_SIZE, TILE_SIZE),
+                weight_color,
+            );
+        }
+    }
+}
+
+pub fn spawn_path_cache_debug_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("pixel.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(5.),
+                left: Val::Px(5.),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        PathCacheDebugText,
+    ));
+}
+
+pub fn debug_path_cache(
+    toggle_debug: Res<ToggleNavmeshDebug>,
+    cache: Res<PathCache>,
+    mut q_text: Query<(&mut Text, &mut Visibility), With<PathCacheDebugText>>,
+) {
+    for (mut text, mut visibility) in &mut q_text {
+        *visibility = if toggle_debug.0 {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+
+        if toggle_debug.0 && cache.is_changed() {
+            text.sections[0].value = format!(
+                "Path cache: {} hits, {} misses, {} paths",
+                cache.hits,
+                cache.misses,
+                cache.cached_paths()
+            );
         }
     }
 }
 
+/// Only changes which could alter a path outdate the searches in flight. Pawns stepping on and off tiles
+/// happens every frame and doesn't.
+pub fn advance_navmesh_generation(
+    mut queue: ResMut<PathfindingQueue>,
+    mut nav_tile_changes: EventReader<NavTileChanged>,
+) {
+    if nav_tile_changes
+        .read()
+        .any(|change| change.affects_pathing())
+    {
+        queue.generation += 1;
+    }
+}
+
+/// The world is generated by writing to the navmesh directly, so the clearance of the whole map is worked out
+/// once it exists. From then on `update_navmesh_clearance` keeps it up to date.
+pub fn build_navmesh_clearance(mut navmesh: ResMut<Navmesh>) {
+    // bypass change detection, otherwise updating the clearance would count as another navmesh change
+    navmesh.bypass_change_detection().update_clearance();
+}
+
+pub fn update_navmesh_clearance(
+    mut navmesh: ResMut<Navmesh>,
+    mut nav_tile_changes: EventReader<NavTileChanged>,
+) {
+    let changed = nav_tile_changes
+        .read()
+        .filter(|change| change.affects_pathing())
+        .map(|change| change.pos)
+        .collect::<Vec<_>>();
 
+    if !changed.is_empty() {
+        navmesh
+            .bypass_change_detection()
+            .update_clearance_around(changed);
+    }
+}
+
+pub fn update_navmesh_hierarchy(
+    navmesh: Res<Navmesh>,
+    settings: Res<NavmeshSettings>,
+    mut hierarchy: ResMut<NavmeshHierarchy>,
+    mut nav_tile_changes: EventReader<NavTileChanged>,
+) {
+    let changed = nav_tile_changes
+        .read()
+        .map(|change| change.pos)
+        .collect::<Vec<_>>();
+    hierarchy.update(&navmesh, &settings, &changed);
+}
+
+pub fn update_navmesh_regions(
+    navmesh: Res<Navmesh>,
+    mut regions: ResMut<NavmeshRegions>,
+    mut nav_tile_changes: EventReader<NavTileChanged>,
+) {
+    let changed = nav_tile_changes
+        .read()
+        .map(|change| change.pos)
+        .collect::<Vec<_>>();
+    regions.update(&navmesh, &changed);
+}
+
+pub fn invalidate_path_cache(
+    mut nav_tile_changes: EventReader<NavTileChanged>,
+    mut cache: ResMut<PathCache>,
+) {
+    let changed = nav_tile_changes
+        .read()
+        .filter(|change| change.affects_pathing())
+        .map(|change| (change.pos.x, change.pos.y))
+        .collect::<HashSet<_>>();
+
+    if !changed.is_empty() {
+        cache.invalidate(&changed);
+    }
+}
 
 pub fn listen_for_pathfinding_requests(
     mut pathfinding_event_reader: EventReader<PathfindRequest>,
-    navmesh: Res<Navmesh>,
     mut pathfinding_event_writer: EventWriter<PathfindAnswer>,
+    mut queue: ResMut<PathfindingQueue>,
+    mut cache: ResMut<PathCache>,
+    navmesh: Res<Navmesh>,
+    regions: Res<NavmeshRegions>,
 ) {
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-    struct UsizeVec {
-        x: usize,
-        y: usize,
-    }
-    let navmesh = &navmesh.0;
     for request in pathfinding_event_reader.read() {
-        let Vec2 { x, y } = request.start;
-        let x = x as usize;
-        let y = y as usize;
-
-        let Vec2 { x: end_x, y: end_y } = request.end;
-        let end_x = end_x as usize;
-        let end_y = end_y as usize;
-
-        let result = astar(
-            &UsizeVec { x, y },
-            |&UsizeVec { x, y }| {
-                let up = (x, y.saturating_add(1));
-                let down = (x, y.saturating_sub(1));
-                let left = (x.saturating_sub(1), y);
-                let right = (x.saturating_add(1), y);
-
-                let neighbors = [up, down, left, right]
-                    .iter()
-                    .filter(|&(x, y)| {
-                        navmesh
-                            .get(*x)
-                            .and_then(|row| row.get(*y))
-                            .map(|tile| tile.walkable || (*x == end_x && *y == end_y))
-                            .unwrap_or(false)
-                    })
-                    .map(|(x, y)| (UsizeVec { x: *x, y: *y }, 0)) // Modify this line
-                    .collect::<Vec<_>>();
-
-                neighbors
-            },
-            |&tile| {
-                (Vec2::new(tile.x as f32, tile.y as f32) - Vec2::new(end_x as f32, end_y as f32))
-                    .length() as i32
-            },
-            |UsizeVec { x, y }| x == &end_x && y == &end_y,
-        )
-        .map(|(data, _)| {
-            data.iter()
-                .map(|item| Vec2::new(item.x as f32, item.y as f32))
-                .collect::<Vec<_>>()
+        let cached = cache.get(
+            &navmesh,
+            &regions,
+            request.start,
+            request.end,
+            agent_of(request),
+        );
+        if let Some(path) = cached {
+            // anything still queued or in flight for this entity is stale now
+            queue.latest_request.remove(&request.entity);
+            pathfinding_event_writer.send(PathfindAnswer {
+                path: Some(path),
+                entity: request.entity,
+                target: request.end,
+            });
+            continue;
+        }
+
+        let request_id = queue.next_request_id;
+        queue.next_request_id += 1;
+        queue.latest_request.insert(request.entity, request_id);
+
+        queue.pending.push_back(QueuedPathfindRequest {
+            request: request.clone(),
+            request_id,
+        });
+    }
+}
+
+pub fn dispatch_pathfinding_tasks(
+    mut queue: ResMut<PathfindingQueue>,
+    navmesh: Res<Navmesh>,
+    hierarchy: Res<NavmeshHierarchy>,
+    settings: Res<NavmeshSettings>,
+    entities: &Entities,
+) {
+    let task_pool = AsyncComputeTaskPool::get();
+
+    let mut dispatched = 0;
+    while dispatched < settings.pathfinding_budget {
+        let Some(queued) = queue.pending.pop_front() else {
+            break;
+        };
+
+        // a newer request for this entity has been made, no need to run this one
+        if queue.latest_request.get(&queued.request.entity) != Some(&queued.request_id) {
+            continue;
+        }
+
+        // nobody is left to walk the path
+        if !entities.contains(queued.request.entity) {
+            queue.latest_request.remove(&queued.request.entity);
+            continue;
+        }
+
+        if queue.snapshot_generation != Some(queue.generation) {
+            queue.snapshot = Arc::new(navmesh.clone());
+            queue.hierarchy_snapshot = Arc::new(hierarchy.clone());
+            queue.snapshot_generation = Some(queue.generation);
+        }
+
+        let snapshot = queue.snapshot.clone();
+        let hierarchy = queue.hierarchy_snapshot.clone();
+        let settings = settings.clone();
+        let PathfindRequest { start, end, .. } = queued.request;
+        let agent = agent_of(&queued.request);
+        // long trips search the entrance graph rather than every tile between the two ends
+        let use_hierarchy =
+            NavmeshHierarchy::supports(agent) && start.distance(end) > CHUNK_SIZE as f32;
+
+        let task = task_pool.spawn(async move {
+            let found = if use_hierarchy {
+                hierarchy.find_path(&snapshot, &settings, start, end, agent.faction)
+            } else {
+                find_path(&snapshot, &settings, start, end, agent)
+            };
+            found.map(|(path, _)| smooth_path(&snapshot, &path, agent))
+        });
+
+        let generation = queue.generation;
+        queue.in_flight.push(PathfindTask {
+            request: queued,
+            generation,
+            task,
         });
+        dispatched += 1;
+    }
+}
+
+pub fn collect_pathfinding_results(
+    mut queue: ResMut<PathfindingQueue>,
+    mut cache: ResMut<PathCache>,
+    regions: Res<NavmeshRegions>,
+    mut pathfinding_event_writer: EventWriter<PathfindAnswer>,
+    entities: &Entities,
+) {
+    let queue = &mut *queue;
+    let (finished, in_flight): (Vec<_>, Vec<_>) = queue
+        .in_flight
+        .drain(..)
+        .partition(|pathfind_task| pathfind_task.task.is_finished());
+    queue.in_flight = in_flight;
+
+    for PathfindTask {
+        request,
+        generation,
+        task,
+    } in finished
+    {
+        let path = block_on(task);
+        let entity = request.request.entity;
 
+        // the request has been superseded by a newer one for the same entity, drop the answer
+        if queue.latest_request.get(&entity) != Some(&request.request_id) {
+            continue;
+        }
+
+        // the entity was despawned while its path was being searched for
+        if !entities.contains(entity) {
+            queue.latest_request.remove(&entity);
+            continue;
+        }
+
+        // the navmesh changed while we were searching, drop the answer and run the request again against
+        // the new snapshot
+        if generation != queue.generation {
+            queue.pending.push_back(request);
+            continue;
+        }
+
+        queue.latest_request.remove(&entity);
+        if let Some(path) = &path {
+            let PathfindRequest { start, end, .. } = request.request;
+            cache.insert(&regions, start, end, agent_of(&request.request), path);
+        }
         pathfinding_event_writer.send(PathfindAnswer {
-            path: result,
-            entity: request.entity,
-            target: request.end,
+            path,
+            entity,
+            target: request.request.end,
         });
     }
 }
+
+fn agent_of(request: &PathfindRequest) -> NavAgent {
+    NavAgent {
+        size: request.agent_size,
+        faction: request.faction,
+    }
+}
+
+/// Start the next game from an empty navmesh. Any paths still being searched for belong to the old world.
+pub fn reset_navmesh(mut commands: Commands) {
+    commands.insert_resource(Navmesh::default());
+    commands.insert_resource(PathfindingQueue::default());
+    commands.insert_resource(NavmeshHierarchy::default());
+    commands.insert_resource(NavmeshRegions::default());
+    commands.insert_resource(PathCache::default());
+}
@@ -1,53 +1,335 @@
-use super::{Navmesh, PathfindRequest};
+use super::components::{Faction, NavmeshSettings, MIN_TILE_WEIGHT};
+use super::Navmesh;
 use bevy::prelude::*;
+use bevy::utils::HashSet;
 use pathfinding::prelude::*;
 
-pub fn get_pathing(request: PathfindRequest, navmesh: &Res<Navmesh>) -> Option<Vec<Vec2>> {
-    let Vec2 { x, y } = request.start;
-    let start_x = x as usize;
-    let start_y = y as usize;
-
-    let Vec2 { x: end_x, y: end_y } = request.end;
-    let end_x = end_x as usize;
-    let end_y = end_y as usize;
-
-    let result = astar(
-        &(start_x, start_y),
-        |&(x, y)| {
-            let up = (x, y.saturating_add(1));
-            let down = (x, y.saturating_sub(1));
-            let left = (x.saturating_sub(1), y);
-            let right = (x.saturating_add(1), y);
-
-            let neighbors = [up, down, left, right]
-                .iter()
-                .filter(|&(x, y)| {
-                    navmesh
-                        .0
-                        .get(*x)
-                        .and_then(|row| row.get(*y))
-                        .map(|tile| {
-                            tile.walkable
-                                || (*x == end_x && *y == end_y)
-                                || (*x == start_x && *y == start_y)
-                        })
-                        .unwrap_or(false)
-                })
-                .map(|(x, y)| ((*x, *y), 0)) // Modify this line
-                .collect::<Vec<_>>();
-
-            neighbors
-        },
-        |&(x, y)| {
-            (Vec2::new(x as f32, y as f32) - Vec2::new(end_x as f32, end_y as f32)).length() as i32
+/// A* costs must be integers, so tile weights are scaled by this amount before being handed to the solver.
+const COST_SCALE: f32 = 100.;
+/// How far apart, in tiles, the footprint of an agent is sampled when sweeping it along a straight segment.
+/// Small enough that a diagonal sweep can not slip past the corner of a tile without touching it.
+const SWEEP_STEP: f32 = 0.1;
+
+/// Orthogonal neighbours first, followed by the diagonals
+const NEIGHBOR_OFFSETS: [(i32, i32); 8] = [
+    (0, 1),
+    (0, -1),
+    (-1, 0),
+    (1, 0),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Step costs round up so the (rounded down) heuristic can never overtake the real cost of a path.
+pub fn scaled_cost(cost: f32) -> u32 {
+    (cost * COST_SCALE).ceil() as u32
+}
+
+fn in_bounds(navmesh: &Navmesh, x: i32, y: i32) -> bool {
+    x >= 0 && y >= 0 && (x as usize) < navmesh.0.len() && (y as usize) < navmesh.0[x as usize].len()
+}
+
+/// The size and faction of the agent a search is run for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NavAgent {
+    /// The width, in tiles, of the square the agent occupies
+    pub size: u32,
+    pub faction: Faction,
+}
+
+/// Whether the agent can stand with its bottom left corner on this tile
+fn tile_fits(navmesh: &Navmesh, x: i32, y: i32, agent: NavAgent) -> bool {
+    if !in_bounds(navmesh, x, y) {
+        return false;
+    }
+    let tile = &navmesh.0[x as usize][y as usize];
+    // single tile agents only need the tile itself, which is always up to date. Clearance does not know
+    // about factions, so larger agents treat doors as closed.
+    if agent.size <= 1 {
+        return tile.is_walkable_for(agent.faction);
+    }
+    tile.walkable && tile.clearance >= agent.size
+}
+
+/// Diagonal steps are only allowed when both tiles sharing the corner are walkable,
+/// otherwise pawns would clip through the corner of a wall or stone.
+fn diagonal_clear(
+    navmesh: &Navmesh,
+    (x, y): (i32, i32),
+    (dx, dy): (i32, i32),
+    agent: NavAgent,
+) -> bool {
+    tile_fits(navmesh, x + dx, y, agent) && tile_fits(navmesh, x, y + dy, agent)
+}
+
+/// An inclusive-exclusive rectangle of tiles that a search is not allowed to leave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileBounds {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+impl TileBounds {
+    pub fn of_navmesh(navmesh: &Navmesh) -> Self {
+        Self {
+            min: (0, 0),
+            max: (
+                navmesh.0.len() as i32,
+                navmesh.0.first().map(Vec::len).unwrap_or(0) as i32,
+            ),
+        }
+    }
+
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        x >= self.min.0 && y >= self.min.1 && x < self.max.0 && y < self.max.1
+    }
+}
+
+/// Convert a scaled integer cost produced by the search back into tile weight units.
+pub fn unscaled_cost(cost: u32) -> f32 {
+    cost as f32 / COST_SCALE
+}
+
+/// Octile (or manhattan without diagonals) distance scaled by the cheapest tile weight, so it is an
+/// admissible heuristic for every search over the navmesh.
+pub fn heuristic(
+    settings: &NavmeshSettings,
+    (x, y): (i32, i32),
+    (end_x, end_y): (i32, i32),
+) -> u32 {
+    let dx = (x - end_x).abs() as f32;
+    let dy = (y - end_y).abs() as f32;
+    let distance = if settings.allow_diagonals {
+        // move diagonally as far as possible, then straight
+        dx.max(dy) + (std::f32::consts::SQRT_2 - 1.) * dx.min(dy)
+    } else {
+        dx + dy
+    };
+    (distance * MIN_TILE_WEIGHT * COST_SCALE).floor() as u32
+}
+
+/// The offsets to every neighbour a tile can step to, including diagonals if they are enabled.
+pub fn neighbor_offsets(settings: &NavmeshSettings) -> &'static [(i32, i32)] {
+    if settings.allow_diagonals {
+        &NEIGHBOR_OFFSETS
+    } else {
+        &NEIGHBOR_OFFSETS[..4]
+    }
+}
+
+/// The scaled cost of stepping from `from` onto the neighbouring tile `to`, ignoring walkability.
+pub fn entry_cost(navmesh: &Navmesh, from: (i32, i32), to: (i32, i32)) -> u32 {
+    let multiplier = if from.0 != to.0 && from.1 != to.1 {
+        std::f32::consts::SQRT_2
+    } else {
+        1.
+    };
+    let weight = navmesh.0[to.0 as usize][to.1 as usize]
+        .weight
+        .max(MIN_TILE_WEIGHT);
+    scaled_cost(weight * multiplier)
+}
+
+/// Whether an agent standing on `from` may step onto the neighbouring tile `to`. `passable` decides which
+/// non-walkable tiles (such as the start or end of a search) may still be entered.
+pub fn can_step(
+    navmesh: &Navmesh,
+    from: (i32, i32),
+    to: (i32, i32),
+    agent: NavAgent,
+    passable: impl Fn((i32, i32)) -> bool,
+) -> bool {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+
+    in_bounds(navmesh, to.0, to.1)
+        && (tile_fits(navmesh, to.0, to.1, agent) || passable(to))
+        && (dx == 0 || dy == 0 || diagonal_clear(navmesh, from, (dx, dy), agent))
+}
+
+/// The neighbours of `tile` inside `bounds` that can be stepped onto, and the scaled cost of each step.
+pub fn successors(
+    navmesh: &Navmesh,
+    settings: &NavmeshSettings,
+    bounds: TileBounds,
+    (x, y): (i32, i32),
+    agent: NavAgent,
+    passable: impl Fn((i32, i32)) -> bool,
+) -> Vec<((i32, i32), u32)> {
+    neighbor_offsets(settings)
+        .iter()
+        .map(|&(dx, dy)| (x + dx, y + dy))
+        .filter(|&tile| bounds.contains(tile) && can_step(navmesh, (x, y), tile, agent, &passable))
+        .map(|tile| (tile, entry_cost(navmesh, (x, y), tile)))
+        .collect()
+}
+
+/// A* restricted to `bounds`, returning the tiles of the path and its scaled cost.
+pub fn find_path_within(
+    navmesh: &Navmesh,
+    settings: &NavmeshSettings,
+    bounds: TileBounds,
+    start: (i32, i32),
+    end: (i32, i32),
+    agent: NavAgent,
+) -> Option<(Vec<(i32, i32)>, u32)> {
+    astar(
+        &start,
+        |&tile| {
+            successors(navmesh, settings, bounds, tile, agent, |t| {
+                t == end || t == start
+            })
         },
-        |(x, y)| x == &end_x && y == &end_y,
+        |&tile| heuristic(settings, tile, end),
+        |&tile| tile == end,
+    )
+}
+
+/// Shared A* core used by every pathfinding consumer. The cost of stepping onto a tile is that tile's
+/// `weight` (multiplied by √2 for diagonal steps), and the heuristic is the octile or manhattan distance
+/// multiplied by the cheapest possible tile weight so that it never overestimates the remaining cost.
+///
+/// The start and end tiles are always considered walkable, which allows searching to or from a stone or a
+/// placeable that occupies its own tile.
+///
+/// Agents wider than a single tile only step onto tiles whose clearance fits their size, with the
+/// path following the agent's bottom left tile.
+pub fn find_path(
+    navmesh: &Navmesh,
+    settings: &NavmeshSettings,
+    start: Vec2,
+    end: Vec2,
+    agent: NavAgent,
+) -> Option<(Vec<Vec2>, f32)> {
+    let (path, cost) = find_path_within(
+        navmesh,
+        settings,
+        TileBounds::of_navmesh(navmesh),
+        (start.x as i32, start.y as i32),
+        (end.x as i32, end.y as i32),
+        agent,
+    )?;
+
+    let path = path
+        .into_iter()
+        .map(|(x, y)| Vec2::new(x as f32, y as f32))
+        .collect::<Vec<_>>();
+
+    Some((path, unscaled_cost(cost)))
+}
+
+/// A* that never strays more than `range` tiles from `start` along either axis, for checking whether
+/// something close by can be reached without searching the whole map.
+pub fn find_path_in_range(
+    navmesh: &Navmesh,
+    settings: &NavmeshSettings,
+    start: Vec2,
+    end: Vec2,
+    agent: NavAgent,
+    range: usize,
+) -> Option<Vec<Vec2>> {
+    let start = (start.x as i32, start.y as i32);
+    let range = range as i32;
+    let navmesh_bounds = TileBounds::of_navmesh(navmesh);
+    let bounds = TileBounds {
+        min: ((start.0 - range).max(0), (start.1 - range).max(0)),
+        max: (
+            (start.0 + range + 1).min(navmesh_bounds.max.0),
+            (start.1 + range + 1).min(navmesh_bounds.max.1),
+        ),
+    };
+
+    let (path, _) = find_path_within(
+        navmesh,
+        settings,
+        bounds,
+        start,
+        (end.x as i32, end.y as i32),
+        agent,
+    )?;
+
+    Some(
+        path.into_iter()
+            .map(|(x, y)| Vec2::new(x as f32, y as f32))
+            .collect(),
     )
-    .map(|(data, _)| {
-        data.iter()
-            .map(|item| Vec2::new(item.0 as f32, item.1 as f32))
-            .collect::<Vec<_>>()
-    });
+}
+
+/// Every tile touched by the footprint of an agent `agent_size` tiles wide while it moves in a straight line
+/// from `from` to `to`. Positions are the agent's bottom left corner in tile units.
+pub fn segment_tiles(from: Vec2, to: Vec2, agent_size: u32) -> HashSet<(i32, i32)> {
+    let size = agent_size.max(1) as f32;
+    let samples = ((to - from).length() / SWEEP_STEP).ceil().max(1.) as usize;
+
+    let mut tiles = HashSet::new();
+    for sample in 0..=samples {
+        let position = from.lerp(to, sample as f32 / samples as f32);
+        let (min_x, min_y) = (position.x.floor() as i32, position.y.floor() as i32);
+        let (max_x, max_y) = (
+            (position.x + size).ceil() as i32,
+            (position.y + size).ceil() as i32,
+        );
+
+        for x in min_x..max_x {
+            for y in min_y..max_y {
+                tiles.insert((x, y));
+            }
+        }
+    }
+
+    tiles
+}
+
+/// Whether the agent can walk in a straight line from `from` to `to`. The tile it starts on is exempt, so
+/// an agent standing on a tile that has since been blocked can still walk off it.
+pub fn segment_clear(navmesh: &Navmesh, from: Vec2, to: Vec2, agent: NavAgent) -> bool {
+    let start = tile_of(from);
+    segment_tiles(from, to, agent.size)
+        .into_iter()
+        .filter(|&tile| tile != start)
+        .all(|(x, y)| tile_fits(navmesh, x, y, agent))
+}
+
+/// Drop every waypoint the agent could skip by walking in a straight line, so it follows the path as a
+/// polyline instead of zig-zagging between tile centres. A shortcut is only taken when every tile it sweeps
+/// is walkable and no heavier than the tiles of the section it replaces, so detours around expensive
+/// terrain are kept. The first and last waypoints are always kept.
+pub fn smooth_path(navmesh: &Navmesh, path: &[Vec2], agent: NavAgent) -> Vec<Vec2> {
+    let (Some(&first), Some(&last)) = (path.first(), path.last()) else {
+        return Vec::new();
+    };
+    let endpoints = [tile_of(first), tile_of(last)];
+
+    let tile_weight = |(x, y): (i32, i32)| navmesh.0[x as usize][y as usize].weight;
+
+    let mut smoothed = vec![first];
+    let mut anchor = 0;
+    for next in 2..path.len() {
+        let section_weight = path[anchor..=next]
+            .iter()
+            .map(|&waypoint| tile_of(waypoint))
+            .filter(|tile| in_bounds(navmesh, tile.0, tile.1))
+            .map(tile_weight)
+            .fold(MIN_TILE_WEIGHT, f32::max);
+
+        let shortcut_clear = segment_tiles(path[anchor], path[next], agent.size)
+            .into_iter()
+            .filter(|tile| !endpoints.contains(tile))
+            .all(|(x, y)| tile_fits(navmesh, x, y, agent) && tile_weight((x, y)) <= section_weight);
+
+        if !shortcut_clear {
+            anchor = next - 1;
+            smoothed.push(path[anchor]);
+        }
+    }
+
+    if path.len() > 1 {
+        smoothed.push(last);
+    }
+    smoothed
+}
 
-    result
+fn tile_of(position: Vec2) -> (i32, i32) {
+    (position.x as i32, position.y as i32)
 }
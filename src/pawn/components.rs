@@ -1,6 +1,10 @@
-use crate::assets::CharacterFacing;
+use crate::assets::{Appearance, CharacterFacing};
+use crate::health::Health;
 use bevy::prelude::*;
+use bevy_inspector_egui::inspector_options::std_options::NumberDisplay;
+use bevy_inspector_egui::prelude::*;
 pub use pawn_status::ClearStatus;
+use rand::prelude::*;
 use std::collections::VecDeque;
 pub use work_order::ClearWorkOrder;
 
@@ -8,26 +12,27 @@ pub use work_order::ClearWorkOrder;
 pub struct Pawn {
     pub move_path: VecDeque<Vec2>,
     pub move_to: Option<Vec2>,
-    pub health: usize,
-    pub max_health: usize,
     pub animation_timer: Timer,
     pub work_timer: Timer,
+    /// The cooldown between attacks, kept apart from `work_timer` so fighting doesn't depend on the last job
+    pub attack_timer: Timer,
     pub search_timer: Timer,
     pub retry_pathfinding_timer: Timer,
     pub moving: bool,
+    pub appearance: Appearance,
 }
 
 #[derive(Component)]
-pub struct Enemy;
+pub struct Enemy {
+    pub attack: usize,
+    /// Pixels per second
+    pub move_speed: f32,
+}
 
+/// The pawn's work order was given directly by the player. It is kept until the work order is done or
+/// dropped, and until then the pawn will not go looking for other work or fights.
 #[derive(Component)]
-pub struct HealthBar;
-
-#[derive(Bundle)]
-pub struct HealthBundle {
-    pub health_bundle: SpriteBundle,
-    pub health_bar: HealthBar,
-}
+pub struct ForcedOrder;
 
 #[derive(Bundle)]
 pub struct PawnBundle {
@@ -35,6 +40,7 @@ pub struct PawnBundle {
     pub name: Name,
     pub sprite_bundle: SpriteSheetBundle,
     pub pawn: Pawn,
+    pub health: Health,
     pub pawn_status: pawn_status::PawnStatus,
     pub resources: CarriedResources,
 }
@@ -42,6 +48,310 @@ pub struct PawnBundle {
 #[derive(Component, Reflect)]
 pub struct CarriedResources(pub usize);
 
+/// The types of work a colonist can be told to do, or not do, through its `WorkPriorities`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum WorkType {
+    Mining,
+    Building,
+    Hauling,
+    Fighting,
+}
+
+impl WorkType {
+    pub const ALL: [WorkType; 4] = [
+        WorkType::Mining,
+        WorkType::Building,
+        WorkType::Hauling,
+        WorkType::Fighting,
+    ];
+}
+
+/// How eager a colonist is to do each type of work, from 1 (first) to 4 (last). `None` means the colonist
+/// never does that type of work. Ties are broken by the priority of the job itself, then by distance.
+#[derive(Component, Reflect, Debug, Clone)]
+pub struct WorkPriorities {
+    pub mining: Option<u8>,
+    pub building: Option<u8>,
+    pub hauling: Option<u8>,
+    pub fighting: Option<u8>,
+}
+
+impl Default for WorkPriorities {
+    fn default() -> Self {
+        Self {
+            mining: Some(3),
+            building: Some(3),
+            hauling: Some(3),
+            fighting: Some(3),
+        }
+    }
+}
+
+impl WorkPriorities {
+    pub const HIGHEST: u8 = 1;
+    pub const LOWEST: u8 = 4;
+
+    pub fn get(&self, work_type: WorkType) -> Option<u8> {
+        match work_type {
+            WorkType::Mining => self.mining,
+            WorkType::Building => self.building,
+            WorkType::Hauling => self.hauling,
+            WorkType::Fighting => self.fighting,
+        }
+    }
+
+    pub fn set(&mut self, work_type: WorkType, priority: Option<u8>) {
+        let priority = priority.map(|priority| priority.clamp(Self::HIGHEST, Self::LOWEST));
+        match work_type {
+            WorkType::Mining => self.mining = priority,
+            WorkType::Building => self.building = priority,
+            WorkType::Hauling => self.hauling = priority,
+            WorkType::Fighting => self.fighting = priority,
+        }
+    }
+
+    /// Step to the next lower priority, wrapping from the lowest to disabled and from disabled to the highest
+    pub fn cycle(&mut self, work_type: WorkType) {
+        let next = match self.get(work_type) {
+            Some(priority) if priority < Self::LOWEST => Some(priority + 1),
+            Some(_) => None,
+            None => Some(Self::HIGHEST),
+        };
+        self.set(work_type, next);
+    }
+
+    /// Whether the colonist should drop `current` work to go and fight. It only does so when fighting ranks
+    /// at least as high as the work it is doing.
+    pub fn will_fight(&self, current: Option<&work_order::WorkOrder>) -> bool {
+        let Some(fighting) = self.fighting else {
+            return false;
+        };
+
+        current
+            .and_then(work_order::WorkOrder::work_type)
+            .and_then(|work_type| self.get(work_type))
+            .map_or(true, |current| fighting <= current)
+    }
+}
+
+/// How well fed and rested a colonist is, from 0 (starving or exhausted) to 100 (not wanting for anything).
+/// Both decay over time, and `mood` follows whichever of them is lowest.
+#[derive(Component, Reflect, InspectorOptions, Debug, Clone)]
+#[reflect(InspectorOptions)]
+pub struct Needs {
+    #[inspector(min = 0.0, max = 100.0, display = NumberDisplay::Slider)]
+    pub hunger: f32,
+    #[inspector(min = 0.0, max = 100.0, display = NumberDisplay::Slider)]
+    pub rest: f32,
+    #[inspector(min = 0.0, max = 100.0, display = NumberDisplay::Slider)]
+    pub mood: f32,
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Self {
+            hunger: Self::MAX,
+            rest: Self::MAX,
+            mood: Self::MAX,
+        }
+    }
+}
+
+impl Needs {
+    pub const MAX: f32 = 100.;
+    /// Below this a colonist drops whatever it is doing to see to the need
+    pub const CRITICAL: f32 = 20.;
+
+    /// Mood is mostly dragged down by the worst need, so a starving but rested colonist is still unhappy
+    pub fn update_mood(&mut self) {
+        let worst = self.hunger.min(self.rest);
+        let average = (self.hunger + self.rest) / 2.;
+        self.mood = (worst * 0.75 + average * 0.25).clamp(0., Self::MAX);
+    }
+
+    pub fn hunger_critical(&self) -> bool {
+        self.hunger <= Self::CRITICAL
+    }
+
+    pub fn rest_critical(&self) -> bool {
+        self.rest <= Self::CRITICAL
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum SkillKind {
+    Mining,
+    Construction,
+    Melee,
+}
+
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct Skill {
+    pub level: u32,
+    pub xp: f32,
+}
+
+impl Skill {
+    pub const MAX_LEVEL: u32 = 10;
+
+    /// How much xp it takes to get from `level` to the next one
+    pub fn xp_to_next_level(level: u32) -> f32 {
+        50. * (level + 1) as f32
+    }
+
+    /// How much faster, or harder, a colonist with this skill works than an unskilled one
+    pub fn multiplier(&self) -> f32 {
+        1. + self.level as f32 * 0.1
+    }
+
+    fn gain_xp(&mut self, xp: f32) {
+        if self.level >= Self::MAX_LEVEL {
+            return;
+        }
+
+        self.xp += xp;
+        while self.level < Self::MAX_LEVEL && self.xp >= Self::xp_to_next_level(self.level) {
+            self.xp -= Self::xp_to_next_level(self.level);
+            self.level += 1;
+        }
+    }
+}
+
+/// What a colonist is good at. Skills scale how quickly it mines and builds and how hard it hits, and they
+/// improve as they are used.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+pub struct Skills {
+    pub mining: Skill,
+    pub construction: Skill,
+    pub melee: Skill,
+}
+
+impl Skills {
+    /// A new colonist starts out with a few random levels in each skill
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let mut roll = || Skill {
+            level: rng.gen_range(0..=3),
+            xp: 0.,
+        };
+        Self {
+            mining: roll(),
+            construction: roll(),
+            melee: roll(),
+        }
+    }
+
+    pub fn gain_xp(&mut self, kind: SkillKind, xp: f32, traits: &Traits) {
+        let xp = xp * traits.learning_rate();
+        match kind {
+            SkillKind::Mining => self.mining.gain_xp(xp),
+            SkillKind::Construction => self.construction.gain_xp(xp),
+            SkillKind::Melee => self.melee.gain_xp(xp),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum PawnTrait {
+    /// Works faster at everything
+    Industrious,
+    /// Works slower at everything
+    Lazy,
+    /// Hits harder
+    Brawler,
+    /// Has more health
+    Tough,
+    /// Has less health
+    Frail,
+    /// Gains xp faster
+    QuickLearner,
+}
+
+impl PawnTrait {
+    const ALL: [PawnTrait; 6] = [
+        PawnTrait::Industrious,
+        PawnTrait::Lazy,
+        PawnTrait::Brawler,
+        PawnTrait::Tough,
+        PawnTrait::Frail,
+        PawnTrait::QuickLearner,
+    ];
+
+    /// Traits which cancel each other out, so a colonist never has both
+    fn conflicts_with(&self, other: PawnTrait) -> bool {
+        matches!(
+            (self, other),
+            (PawnTrait::Industrious, PawnTrait::Lazy)
+                | (PawnTrait::Lazy, PawnTrait::Industrious)
+                | (PawnTrait::Tough, PawnTrait::Frail)
+                | (PawnTrait::Frail, PawnTrait::Tough)
+        )
+    }
+}
+
+#[derive(Component, Debug, Clone, Default, Reflect)]
+pub struct Traits(pub Vec<PawnTrait>);
+
+impl Traits {
+    const MAX_TRAITS: usize = 2;
+
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let mut traits = Vec::new();
+        let count = rng.gen_range(0..=Self::MAX_TRAITS);
+        for candidate in PawnTrait::ALL.choose_multiple(rng, PawnTrait::ALL.len()) {
+            if traits.len() >= count {
+                break;
+            }
+            if !traits
+                .iter()
+                .any(|held: &PawnTrait| held.conflicts_with(*candidate))
+            {
+                traits.push(*candidate);
+            }
+        }
+        Self(traits)
+    }
+
+    pub fn has(&self, pawn_trait: PawnTrait) -> bool {
+        self.0.contains(&pawn_trait)
+    }
+
+    pub fn work_speed(&self) -> f32 {
+        if self.has(PawnTrait::Industrious) {
+            1.25
+        } else if self.has(PawnTrait::Lazy) {
+            0.75
+        } else {
+            1.
+        }
+    }
+
+    pub fn damage(&self) -> f32 {
+        if self.has(PawnTrait::Brawler) {
+            1.3
+        } else {
+            1.
+        }
+    }
+
+    pub fn max_health(&self, base: usize) -> usize {
+        if self.has(PawnTrait::Tough) {
+            base + base / 4
+        } else if self.has(PawnTrait::Frail) {
+            base - base / 4
+        } else {
+            base
+        }
+    }
+
+    pub fn learning_rate(&self) -> f32 {
+        if self.has(PawnTrait::QuickLearner) {
+            1.5
+        } else {
+            1.
+        }
+    }
+}
+
 pub mod pawn_status {
     use bevy::{ecs::system::EntityCommands, prelude::*};
 
@@ -90,7 +400,9 @@ pub mod pawn_status {
         Moving,
         Mining,
         Attacking,
-        Building
+        Building,
+        Eating,
+        Sleeping
     );
 }
 
@@ -149,6 +461,9 @@ pub mod work_order {
         struct MineStone {
             stone_entity: Entity,
         },
+        struct MoveTo {
+            destination: crate::utils::GridPos,
+        },
         struct ReturnToFactory {},
         struct PickupStoneFromFactory {
             for_entity: Entity,
@@ -156,9 +471,59 @@ pub mod work_order {
         struct BuildItem {
             item_entity: Entity,
         },
+        struct DeconstructItem {
+            item_entity: Entity,
+        },
+        struct Haul {
+            loot_entity: Entity,
+        },
         struct AttackPawn {
             pawn_entity: Entity,
         },
-        struct AttackFactory {}
+        struct AttackFactory {},
+        struct AttackStructure {
+            structure_entity: Entity,
+        },
+        struct Eat {},
+        struct Sleep {}
     );
+
+    impl WorkOrder {
+        /// The type of work this work order counts as for `WorkPriorities`
+        pub fn work_type(&self) -> Option<super::WorkType> {
+            match self {
+                WorkOrder::MineStone(_) | WorkOrder::ReturnToFactory(_) => {
+                    Some(super::WorkType::Mining)
+                }
+                WorkOrder::PickupStoneFromFactory(_)
+                | WorkOrder::BuildItem(_)
+                | WorkOrder::DeconstructItem(_) => Some(super::WorkType::Building),
+                WorkOrder::AttackPawn(_)
+                | WorkOrder::AttackFactory(_)
+                | WorkOrder::AttackStructure(_) => Some(super::WorkType::Fighting),
+                WorkOrder::Haul(_) => Some(super::WorkType::Hauling),
+                WorkOrder::MoveTo(_) | WorkOrder::Eat(_) | WorkOrder::Sleep(_) => None,
+            }
+        }
+
+        /// The entity this work order is for, if it comes from a job on the `JobBoard`
+        pub fn job_target(&self) -> Option<Entity> {
+            match self {
+                WorkOrder::MineStone(MineStone { stone_entity }) => Some(*stone_entity),
+                WorkOrder::PickupStoneFromFactory(PickupStoneFromFactory { for_entity }) => {
+                    Some(*for_entity)
+                }
+                WorkOrder::BuildItem(BuildItem { item_entity })
+                | WorkOrder::DeconstructItem(DeconstructItem { item_entity }) => Some(*item_entity),
+                WorkOrder::Haul(Haul { loot_entity }) => Some(*loot_entity),
+                WorkOrder::MoveTo(_)
+                | WorkOrder::ReturnToFactory(_)
+                | WorkOrder::AttackPawn(_)
+                | WorkOrder::AttackFactory(_)
+                | WorkOrder::AttackStructure(_)
+                | WorkOrder::Eat(_)
+                | WorkOrder::Sleep(_) => None,
+            }
+        }
+    }
 }
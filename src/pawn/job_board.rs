@@ -0,0 +1,273 @@
+use super::components::{WorkPriorities, WorkType};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use smallvec::SmallVec;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// The kinds of work colonists pick up from the `JobBoard`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum JobKind {
+    Mine,
+    Build,
+    Deconstruct,
+    Repair,
+    Haul,
+}
+
+impl JobKind {
+    /// Higher priority jobs are handed out first, no matter how far away they are
+    pub fn default_priority(&self) -> u32 {
+        match self {
+            JobKind::Build => 3,
+            JobKind::Repair => 2,
+            JobKind::Deconstruct | JobKind::Haul => 1,
+            JobKind::Mine => 0,
+        }
+    }
+
+    pub fn work_type(&self) -> WorkType {
+        match self {
+            JobKind::Mine => WorkType::Mining,
+            JobKind::Build | JobKind::Repair | JobKind::Deconstruct => WorkType::Building,
+            JobKind::Haul => WorkType::Hauling,
+        }
+    }
+
+    /// How long, in seconds, a pawn may hold on to the job before it is offered to someone else
+    pub fn timeout(&self) -> f32 {
+        match self {
+            JobKind::Build => 90.,
+            JobKind::Repair => 60.,
+            JobKind::Deconstruct => 30.,
+            JobKind::Haul => 60.,
+            JobKind::Mine => 120.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Job {
+    pub kind: JobKind,
+    /// The stone, placeable or loot pile the job is for
+    pub target: Entity,
+}
+
+impl Job {
+    pub fn new(kind: JobKind, target: Entity) -> Self {
+        Self { kind, target }
+    }
+}
+
+struct Claim {
+    pawn: Entity,
+    claimed_at: f32,
+}
+
+struct Posting {
+    /// Where the job is done, in tile units
+    position: Vec2,
+    priority: u32,
+    claim: Option<Claim>,
+}
+
+/// Every job waiting to be done. A job stays on the board until it is finished, and a pawn working on it holds
+/// a claim so no other pawn picks it up. Claims are released when the pawn dies, moves on to other work or
+/// takes too long, which puts the job back up for grabs.
+#[derive(Resource, Default)]
+pub struct JobBoard {
+    jobs: HashMap<Job, Posting>,
+    /// The job each pawn holds a claim on, so a pawn's claim is found without going through every job
+    claims: HashMap<Entity, Job>,
+    /// The jobs posted for each target, so a target's jobs are found without going through every job
+    by_target: HashMap<Entity, SmallVec<[Job; 2]>>,
+}
+
+impl JobBoard {
+    /// Put a job on the board. Posting a job which is already on the board leaves it as it is.
+    pub fn post(&mut self, job: Job, position: Vec2) {
+        if self.jobs.contains_key(&job) {
+            return;
+        }
+        self.jobs.insert(
+            job,
+            Posting {
+                position,
+                priority: job.kind.default_priority(),
+                claim: None,
+            },
+        );
+        self.by_target.entry(job.target).or_default().push(job);
+    }
+
+    pub fn remove(&mut self, job: Job) {
+        let Some(posting) = self.jobs.remove(&job) else {
+            return;
+        };
+        if let Some(claim) = posting.claim {
+            self.claims.remove(&claim.pawn);
+        }
+        if let Some(jobs) = self.by_target.get_mut(&job.target) {
+            jobs.retain(|posted| *posted != job);
+            if jobs.is_empty() {
+                self.by_target.remove(&job.target);
+            }
+        }
+    }
+
+    /// Take every job for `target` off the board
+    pub fn remove_target(&mut self, target: Entity) {
+        let Some(jobs) = self.by_target.remove(&target) else {
+            return;
+        };
+        for job in jobs {
+            if let Some(claim) = self.jobs.remove(&job).and_then(|posting| posting.claim) {
+                self.claims.remove(&claim.pawn);
+            }
+        }
+    }
+
+    pub fn contains(&self, job: Job) -> bool {
+        self.jobs.contains_key(&job)
+    }
+
+    /// Every job of `kind` along with where it is done
+    pub fn jobs_of_kind(&self, kind: JobKind) -> impl Iterator<Item = (Job, Vec2)> + '_ {
+        self.jobs
+            .iter()
+            .filter(move |(job, _)| job.kind == kind)
+            .map(|(&job, posting)| (job, posting.position))
+    }
+
+    /// The job `pawn` currently holds a claim on
+    pub fn claimed_by(&self, pawn: Entity) -> Option<Job> {
+        self.claims.get(&pawn).copied()
+    }
+
+    /// Claim `job` for `pawn`, giving up any other job it held. Fails if another pawn already has it.
+    pub fn claim(&mut self, job: Job, pawn: Entity, now: f32) -> bool {
+        if self.jobs.get(&job).map_or(true, |posting| {
+            posting
+                .claim
+                .as_ref()
+                .is_some_and(|claim| claim.pawn != pawn)
+        }) {
+            return false;
+        }
+
+        self.release(pawn);
+        if let Some(posting) = self.jobs.get_mut(&job) {
+            posting.claim = Some(Claim {
+                pawn,
+                claimed_at: now,
+            });
+            self.claims.insert(pawn, job);
+        }
+        true
+    }
+
+    /// Claim `job` for `pawn` even if another pawn already has it, returning the pawn it was taken from
+    pub fn claim_forced(&mut self, job: Job, pawn: Entity, now: f32) -> Option<Entity> {
+        let previous = self
+            .jobs
+            .get_mut(&job)?
+            .claim
+            .take()
+            .map(|claim| claim.pawn);
+        if let Some(previous) = previous {
+            self.claims.remove(&previous);
+        }
+        self.claim(job, pawn, now);
+        previous.filter(|&previous| previous != pawn)
+    }
+
+    /// Give up the job `pawn` holds, so that it can be picked up again
+    pub fn release(&mut self, pawn: Entity) {
+        let Some(job) = self.claims.remove(&pawn) else {
+            return;
+        };
+        if let Some(posting) = self.jobs.get_mut(&job) {
+            posting.claim = None;
+        }
+    }
+
+    /// Release every claim held for longer than its job's timeout, returning the pawns which lost their job
+    pub fn release_expired(&mut self, now: f32) -> Vec<Entity> {
+        let mut expired = Vec::new();
+        for (job, posting) in self.jobs.iter_mut() {
+            let Some(claim) = &posting.claim else {
+                continue;
+            };
+            if now - claim.claimed_at > job.kind.timeout() {
+                expired.push(claim.pawn);
+                posting.claim = None;
+            }
+        }
+        for pawn in &expired {
+            self.claims.remove(pawn);
+        }
+        expired
+    }
+
+    /// The best unclaimed job for a pawn standing at `position`. Jobs are ranked by the pawn's `priorities` for
+    /// their type of work, then by the priority of the job itself, then by distance. `accept` filters out jobs
+    /// the pawn can not do right now, such as ones it can not reach.
+    pub fn best_job(
+        &self,
+        position: Vec2,
+        priorities: &WorkPriorities,
+        mut accept: impl FnMut(&Job, Vec2) -> bool,
+    ) -> Option<(Job, Vec2)> {
+        let mut candidates = self
+            .jobs
+            .iter()
+            .filter(|(_, posting)| posting.claim.is_none())
+            .filter_map(|(&job, posting)| {
+                let work_priority = priorities.get(job.kind.work_type())?;
+                Some(Reverse(Candidate {
+                    job,
+                    position: posting.position,
+                    rank: (work_priority, Reverse(posting.priority)),
+                    distance: posting.position.distance_squared(position),
+                }))
+            })
+            .collect::<BinaryHeap<_>>();
+
+        // `accept` is the expensive part, so it is only asked about jobs in rank order until one fits. Popping
+        // the heap ranks only as many jobs as it takes, rather than sorting all of them.
+        std::iter::from_fn(|| candidates.pop())
+            .map(|Reverse(candidate)| (candidate.job, candidate.position))
+            .find(|(job, job_position)| accept(job, *job_position))
+    }
+}
+
+/// An unclaimed job being considered for a pawn. Lower candidates are better.
+struct Candidate {
+    job: Job,
+    position: Vec2,
+    rank: (u8, Reverse<u32>),
+    /// Squared distance from the pawn
+    distance: f32,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank
+            .cmp(&other.rank)
+            .then(self.distance.total_cmp(&other.distance))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
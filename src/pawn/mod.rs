@@ -1,12 +1,14 @@
 pub mod components;
+mod job_board;
+mod names;
 mod systems;
 mod work_orders;
 
-use crate::GameState;
-use bevy::prelude::*;
-use std::collections::VecDeque;
+use crate::{utils::GridPos, GameState};
+use bevy::{prelude::*, utils::HashMap};
 
 use self::components::work_order::WorkOrder;
+pub use job_board::{Job, JobBoard, JobKind};
 
 #[derive(SystemSet, Hash, Debug, Clone, Eq, PartialEq)]
 pub enum PawnSystemSet {
@@ -23,15 +25,19 @@ impl Plugin for PawnPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(work_orders::WorkOrderPlugin)
             .add_systems(OnEnter(GameState::PawnSpawn), systems::initial_pawn_spawn)
-            .init_resource::<WorkQueue>()
-            .init_resource::<EnemyWave>()
+            .add_systems(OnExit(GameState::GameOver), systems::reset_pawn_resources)
+            .init_resource::<JobBoard>()
+            .init_resource::<PawnSpatialIndex>()
             .register_type::<components::Pawn>()
             .register_type::<components::work_order::WorkOrder>()
             .register_type::<components::pawn_status::PawnStatus>()
             .register_type::<components::CarriedResources>()
+            .register_type::<components::WorkPriorities>()
+            .register_type::<components::Needs>()
+            .register_type::<components::Skills>()
+            .register_type::<components::Traits>()
             .add_event::<SpawnPawnRequestEvent>()
             .add_event::<RequestWorkOrder>()
-            .add_event::<AttackEvent>()
             .add_event::<PawnDeath>()
             // setup systems scheduling
             .configure_sets(
@@ -47,6 +53,31 @@ impl Plugin for PawnPlugin {
                     .run_if(in_state(GameState::Main))
                     .after(crate::navmesh::NavmeshSystemSet::Last),
             )
+            // damage dealt in the attack systems is applied before the dead are cleaned up
+            .configure_sets(
+                Update,
+                crate::health::HealthSystemSet
+                    .after(PawnSystemSet::Attack)
+                    .before(PawnSystemSet::Last),
+            )
+            .add_systems(
+                Update,
+                systems::handle_destroyed_pawns.in_set(PawnSystemSet::Last),
+            )
+            // keep the job board up to date before anyone looks for work
+            .add_systems(
+                Update,
+                (
+                    systems::post_jobs,
+                    systems::retire_finished_jobs,
+                    systems::release_job_claims,
+                    systems::clear_forced_orders,
+                    systems::decay_needs,
+                    systems::interrupt_for_needs,
+                )
+                    .chain()
+                    .in_set(PawnSystemSet::First),
+            )
             // add work systems
             .add_systems(
                 Update,
@@ -55,7 +86,12 @@ impl Plugin for PawnPlugin {
                     systems::build_placeable,
                     systems::pickup_stone_from_factory,
                     systems::mine_stone,
+                    systems::deconstruct_placeable,
+                    systems::haul_loot,
                     systems::return_to_factory,
+                    systems::complete_move_orders,
+                    systems::eat,
+                    systems::sleep,
                 )
                     .chain()
                     .in_set(PawnSystemSet::Work),
@@ -63,19 +99,27 @@ impl Plugin for PawnPlugin {
             // add attack systems
             .add_systems(
                 Update,
-                (systems::attack_pawn, systems::search_for_attack_target_pawn)
+                (
+                    systems::attack_pawn,
+                    systems::attack_factory,
+                    systems::attack_structure,
+                    systems::search_for_attack_target_pawn,
+                )
                     .chain()
                     .in_set(PawnSystemSet::Attack),
             )
             .add_systems(
                 Update,
                 (
-                    systems::repath_if_navmesh_changes
-                        .run_if(resource_changed::<crate::navmesh::Navmesh>()),
+                    systems::repath_if_navmesh_changes,
                     systems::retry_pathfinding,
                     systems::enemy_search_for_factory,
                     systems::listen_for_pathfinding_answers,
+                    systems::follow_factory_flow_field,
+                    systems::plan_siege,
                     systems::move_pawn,
+                    systems::update_pawn_spatial_index,
+                    systems::separate_pawns,
                 )
                     .chain()
                     .in_set(PawnSystemSet::Move),
@@ -84,7 +128,6 @@ impl Plugin for PawnPlugin {
             .add_systems(
                 Update,
                 (
-                    systems::update_health_ui,
                     systems::update_pawn_animation,
                     systems::listen_for_spawn_pawn_event,
                     systems::spawn_enemy_pawns,
@@ -96,20 +139,42 @@ impl Plugin for PawnPlugin {
     }
 }
 
+/// Every pawn's position bucketed by the tile it is standing on, so that systems can find the pawns near a
+/// point without checking every pawn. Positions are in tile units and refer to the centre of the pawn.
 #[derive(Resource, Default)]
-pub struct WorkQueue {
-    pub build_queue: VecDeque<Entity>,
+pub struct PawnSpatialIndex {
+    cells: HashMap<GridPos, Vec<(Entity, Vec2)>>,
 }
 
-#[derive(Event, Debug)]
-pub struct SpawnPawnRequestEvent;
+impl PawnSpatialIndex {
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
 
-#[derive(Event, Debug)]
-pub struct AttackEvent {
-    pub attacker: Entity,
-    pub target: Entity,
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        self.cells
+            .entry(GridPos::from_tile_pos_vec(position))
+            .or_default()
+            .push((entity, position));
+    }
+
+    /// Every pawn within `radius` tiles of `position`
+    pub fn nearby(&self, position: Vec2, radius: f32) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let min = GridPos::from_tile_pos_vec((position - radius).floor());
+        let max = GridPos::from_tile_pos_vec((position + radius).floor());
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| GridPos::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |&(_, other)| other.distance(position) <= radius)
+    }
 }
 
+#[derive(Event, Debug)]
+pub struct SpawnPawnRequestEvent;
+
 #[derive(Event, Debug)]
 pub struct PawnDeath {
     pub pawn: Entity,
@@ -121,22 +186,3 @@ pub struct PawnDeath {
 
 #[derive(Event, Debug)]
 pub struct RequestWorkOrder {}
-
-#[derive(Resource)]
-pub struct EnemyWave {
-    pub wave: usize,
-    pub enemy_count_multiplier: usize,
-    pub enemy_spawn_timer: Timer,
-    pub enemies: usize,
-}
-
-impl Default for EnemyWave {
-    fn default() -> Self {
-        Self {
-            wave: 0,
-            enemy_count_multiplier: 1,
-            enemy_spawn_timer: Timer::from_seconds(30.0, TimerMode::Repeating),
-            enemies: 0,
-        }
-    }
-}
@@ -0,0 +1,40 @@
+use rand::prelude::*;
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada", "Bram", "Cora", "Dell", "Edda", "Finn", "Greta", "Hal", "Ines", "Jory", "Kit", "Lars",
+    "Mira", "Nils", "Orla", "Pim", "Quinn", "Rook", "Sable", "Tam", "Ulla", "Vik", "Wren", "Yara",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Ashdown",
+    "Blackwood",
+    "Cobb",
+    "Dunmore",
+    "Ellery",
+    "Fairweather",
+    "Graves",
+    "Holloway",
+    "Ironside",
+    "Kettle",
+    "Lowe",
+    "Marsh",
+    "Nettle",
+    "Oakes",
+    "Pike",
+    "Quarry",
+    "Redfern",
+    "Stone",
+    "Thorne",
+    "Underhill",
+    "Vance",
+    "Whitlock",
+];
+
+/// A random first and last name for a new colonist
+pub fn random_name(rng: &mut impl Rng) -> String {
+    let first = FIRST_NAMES
+        .choose(rng)
+        .expect("There should be first names");
+    let last = LAST_NAMES.choose(rng).expect("There should be last names");
+    format!("{first} {last}")
+}
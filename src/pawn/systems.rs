@@ -1,26 +1,29 @@
 use super::components::pawn_status::{Idle, PawnStatus};
 use super::components::work_order::{AddWorkOrder, WorkOrder};
-use super::{AttackEvent, EnemyWave, PawnDeath, SpawnPawnRequestEvent, WorkQueue};
-use crate::factory::components::{Factory, Placed};
-use crate::navmesh::components::{NavTileOccupant, Navmesh, PathfindAnswer, PathfindRequest};
+use super::{names, Job, JobBoard, JobKind, PawnDeath, PawnSpatialIndex, SpawnPawnRequestEvent};
+use crate::factory::components::{factory_footprint, Factory, FactoryFlowField, Placed};
+use crate::health::{DamageEvent, DamageType, EntityDestroyed, Health, HealthBundle};
+use crate::navmesh::components::{Navmesh, NavmeshSettings, PathfindAnswer, PathfindRequest};
 use crate::navmesh::prelude::*;
 use crate::pawn::components::pawn_status::AddStatus;
-use crate::pawn::components::work_order::PickupStoneFromFactory;
+use crate::placeable::components::{Built, NowPlacing};
 use crate::placeable::prelude::PlaceableType;
 use crate::selectable::Selectable;
-use crate::stone::{Stone, StoneKind};
+use crate::stone::{LootPile, Stone, StoneKind};
+use crate::waves::{EnemyArchetype, SpawnEdge, WaveArrived, WaveDirector};
 use crate::{
-    assets::{CharacterFacing, MalePawns},
+    assets::{Appearance, CharacterFacing, PawnAppearance},
     pawn::components::*,
     utils::*,
 };
-use crate::{CursorPosition, GameResources, GameState, SIZE, TILE_SIZE};
+use crate::{CursorPosition, GameResources, GameState, GameStats, SIZE, TILE_SIZE};
 use bevy::ecs::query::ReadOnlyWorldQuery;
 use bevy::prelude::*;
 use bevy::utils::{HashMap, HashSet};
 use leafwing_input_manager::prelude::*;
 use rand::prelude::*;
 use std::collections::VecDeque;
+use std::time::Duration;
 
 const INITIAL_PAWN_COUNT: usize = 10;
 const MOVE_SPEED: f32 = 60.;
@@ -29,12 +32,103 @@ const RESOURCE_GAIN_RATE: usize = 1;
 const PAWN_COST: usize = 100;
 const PAWN_ATTACK_STRENGTH: usize = 7;
 const ENEMY_TILE_RANGE: usize = 10;
-const ENEMY_ATTACK_STRENGTH: usize = 10;
+/// How close, in tiles, an enemy has to be to any tile of the factory to hit it
+const FACTORY_ATTACK_RANGE: f32 = 2.;
 const PAWN_SEARCH_TIMER: f32 = 0.25;
+const PAWN_MAX_HEALTH: usize = 100;
+/// How long, in seconds, an unskilled colonist takes to swing a pickaxe or put its stone into a placeable
+const MINE_TIME: f32 = 0.25;
+const BUILD_TIME: f32 = 1.;
+/// How long, in seconds, a pawn takes between swings at a pawn, a structure or the factory
+const ATTACK_TIME: f32 = 0.25;
+/// Xp gained for each swing of a pickaxe or a weapon, and for each stone put into a placeable
+const MINING_XP: f32 = 1.;
+const MELEE_XP: f32 = 2.;
+const CONSTRUCTION_XP: f32 = 1.;
+/// Pawns and enemies take up a single tile
+pub(super) const PAWN_SIZE: u32 = 1;
+/// How many points of hunger and rest a colonist loses each second
+const HUNGER_DECAY_RATE: f32 = 0.4;
+const REST_DECAY_RATE: f32 = 0.25;
+/// How many points of hunger and rest a colonist regains each second while eating or sleeping
+const EATING_RATE: f32 = 20.;
+const SLEEPING_RATE: f32 = 8.;
+/// Pawns closer together than this many tiles push each other apart
+const SEPARATION_RADIUS: f32 = 0.6;
+/// The fastest, in pixels per second, two overlapping pawns are pushed apart
+const SEPARATION_SPEED: f32 = 40.;
+
+type NewUnbuiltFilter = (Added<PlaceableType>, Without<Built>, Without<NowPlacing>);
+type DamagedBuiltFilter = (Changed<Health>, With<PlaceableType>, With<Built>);
+type EatingPawn<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Pawn,
+    &'a mut Needs,
+    &'a mut PawnStatus,
+    &'a WorkOrder,
+);
+type DeconstructingPawn<'a> = (
+    Entity,
+    &'a Transform,
+    &'a mut Pawn,
+    &'a mut CarriedResources,
+    &'a mut PawnStatus,
+    &'a WorkOrder,
+);
+/// A pawn looking for something to fight, or a pawn it could fight
+type AttackSearchPawn<'a> = (
+    Entity,
+    &'a Pawn,
+    &'a Transform,
+    Option<&'a WorkOrder>,
+    Option<&'a WorkPriorities>,
+    Has<ForcedOrder>,
+);
+type AttackingEnemy<'a> = (
+    Entity,
+    &'a mut Pawn,
+    &'a mut PawnStatus,
+    &'a Transform,
+    &'a WorkOrder,
+    &'a Enemy,
+);
+type PlacedFactoryFilter = (With<Factory>, With<Placed>);
+type StandingStructureFilter = (With<Built>, With<Health>, Without<Pawn>);
+type DestroyedPawn<'a> = (
+    Option<&'a WorkOrder>,
+    &'a Transform,
+    Option<&'a Enemy>,
+    &'a CarriedResources,
+);
+
+fn faction_of(enemy: Option<&Enemy>) -> Faction {
+    if enemy.is_some() {
+        Faction::Enemy
+    } else {
+        Faction::Colonist
+    }
+}
+
+/// How long a colonist takes to do something an unskilled one would take `base` seconds for
+fn work_time(base: f32, skill: &Skill, traits: &Traits) -> Duration {
+    Duration::from_secs_f32(base / (skill.multiplier() * traits.work_speed()))
+}
+
+/// How much stone a placeable still needs. Built placeables have all of theirs, so for them it is the stone
+/// it takes to repair the health they have lost, in proportion to what building them cost.
+fn stone_needed(placeable: &PlaceableType, health: Option<&Health>) -> usize {
+    let Some(health) = health else {
+        return placeable.get_missing_resource_count();
+    };
+
+    let missing_health = health.max.saturating_sub(health.current);
+    (placeable.get_max_resources() * missing_health).div_ceil(health.max.max(1))
+}
 
 fn spawn_pawn_in_random_location(
     commands: &mut Commands,
-    pawn_res: &Res<MalePawns>,
+    pawn_res: &Res<PawnAppearance>,
     game_resources: &mut ResMut<GameResources>,
     factory_transform: &GlobalTransform,
     _: &Res<Navmesh>,
@@ -42,7 +136,10 @@ fn spawn_pawn_in_random_location(
     let radius = TILE_SIZE * 5.;
     let mut rng = rand::thread_rng();
 
-    let pawn = pawn_res.get_random();
+    let appearance = Appearance::random(&mut rng);
+    let skills = Skills::random(&mut rng);
+    let traits = Traits::random(&mut rng);
+    let max_health = traits.max_health(PAWN_MAX_HEALTH);
 
     // spawn pawns in a random circle 1 tile around the factory
     let random_angle: f32 = rng.gen_range(0.0..360.0);
@@ -55,18 +152,18 @@ fn spawn_pawn_in_random_location(
                 pawn: Pawn {
                     move_path: VecDeque::new(),
                     move_to: None,
-                    health: 100,
-                    max_health: 100,
                     animation_timer: Timer::from_seconds(0.125, TimerMode::Repeating),
-                    work_timer: Timer::from_seconds(0.25, TimerMode::Once),
+                    work_timer: Timer::from_seconds(MINE_TIME, TimerMode::Once),
+                    attack_timer: Timer::from_seconds(ATTACK_TIME, TimerMode::Once),
                     moving: false,
                     search_timer: Timer::from_seconds(PAWN_SEARCH_TIMER, TimerMode::Repeating),
                     retry_pathfinding_timer: Timer::from_seconds(1., TimerMode::Once),
+                    appearance,
                 },
                 character_facing: CharacterFacing::Left,
-                name: Name::new("Pawn"),
+                name: Name::new(names::random_name(&mut rng)),
                 sprite_bundle: SpriteSheetBundle {
-                    texture_atlas: pawn,
+                    texture_atlas: pawn_res.get(appearance),
                     transform: Transform::from_translation(Vec3::new(x, y, 1.)),
                     sprite: TextureAtlasSprite {
                         anchor: bevy::sprite::Anchor::BottomLeft,
@@ -75,26 +172,20 @@ fn spawn_pawn_in_random_location(
                     },
                     ..Default::default()
                 },
+                health: Health::new(max_health),
                 pawn_status: PawnStatus::Idle(Idle),
                 resources: CarriedResources(0),
             },
             Selectable,
+            WorkPriorities::default(),
+            Needs::default(),
+            skills,
+            traits,
         ))
         .id();
 
     commands
-        .spawn(HealthBundle {
-            health_bar: HealthBar,
-            health_bundle: SpriteBundle {
-                transform: Transform::from_xyz(16. / 2., 20., 1.),
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(16., 2.)),
-                    color: Color::NONE,
-                    ..default()
-                },
-                ..default()
-            },
-        })
+        .spawn(HealthBundle::above(TILE_SIZE, TILE_SIZE))
         .set_parent(pawn_entity);
 
     game_resources.pawns += 1;
@@ -102,7 +193,7 @@ fn spawn_pawn_in_random_location(
 
 pub fn initial_pawn_spawn(
     mut commands: Commands,
-    pawn_res: Res<MalePawns>,
+    pawn_res: Res<PawnAppearance>,
     q_factory: Query<&GlobalTransform, (With<Factory>, With<Placed>)>,
     mut game_resources: ResMut<GameResources>,
     navmesh: Res<Navmesh>,
@@ -125,129 +216,236 @@ pub fn initial_pawn_spawn(
     next_state.set(GameState::Main);
 }
 
+pub fn post_jobs(
+    mut job_board: ResMut<JobBoard>,
+    q_new_stones: Query<(Entity, &Transform), Added<Stone>>,
+    q_new_loot_piles: Query<(Entity, &Transform), Added<LootPile>>,
+    q_new_placeables: Query<(Entity, &Transform), NewUnbuiltFilter>,
+    q_damaged_built: Query<(Entity, &Transform, &Health), DamagedBuiltFilter>,
+) {
+    for (entity, transform) in &q_new_stones {
+        job_board.post(
+            Job::new(JobKind::Mine, entity),
+            transform.translation.world_pos_to_tile(),
+        );
+    }
+
+    for (entity, transform) in &q_new_placeables {
+        job_board.post(
+            Job::new(JobKind::Build, entity),
+            transform.translation.world_pos_to_tile(),
+        );
+    }
+
+    for (entity, transform) in &q_new_loot_piles {
+        job_board.post(
+            Job::new(JobKind::Haul, entity),
+            transform.translation.world_pos_to_tile(),
+        );
+    }
+
+    // damaged placeables need repairing. Destroyed ones are on their way out and can't be saved.
+    for (entity, transform, health) in &q_damaged_built {
+        let job = Job::new(JobKind::Repair, entity);
+        if !health.is_full() && !health.is_dead() {
+            job_board.post(job, transform.translation.world_pos_to_tile());
+        } else {
+            job_board.remove(job);
+        }
+    }
+}
+
+pub fn retire_finished_jobs(
+    mut job_board: ResMut<JobBoard>,
+    mut removed_stones: RemovedComponents<Stone>,
+    mut removed_placeables: RemovedComponents<PlaceableType>,
+    mut removed_loot_piles: RemovedComponents<LootPile>,
+    q_finished_builds: Query<Entity, Added<Built>>,
+) {
+    for entity in removed_stones
+        .read()
+        .chain(removed_placeables.read())
+        .chain(removed_loot_piles.read())
+    {
+        job_board.remove_target(entity);
+    }
+
+    for entity in &q_finished_builds {
+        job_board.remove(Job::new(JobKind::Build, entity));
+    }
+}
+
+/// Once a direct order is carried out the pawn goes back to picking its own work
+pub fn clear_forced_orders(
+    mut commands: Commands,
+    q_pawns: Query<Entity, (With<ForcedOrder>, Without<WorkOrder>)>,
+) {
+    for pawn in &q_pawns {
+        commands.entity(pawn).remove::<ForcedOrder>();
+    }
+}
+
+/// Put jobs back up for grabs when the pawn holding them dies, moves on to something else or takes too long
+pub fn release_job_claims(
+    mut commands: Commands,
+    mut job_board: ResMut<JobBoard>,
+    mut removed_pawns: RemovedComponents<Pawn>,
+    mut removed_work_orders: RemovedComponents<WorkOrder>,
+    q_changed_work_orders: Query<Entity, Changed<WorkOrder>>,
+    q_work_orders: Query<&WorkOrder>,
+    time: Res<Time>,
+) {
+    for pawn in removed_pawns.read() {
+        job_board.release(pawn);
+    }
+
+    for pawn in removed_work_orders
+        .read()
+        .chain(q_changed_work_orders.iter())
+    {
+        let Some(job) = job_board.claimed_by(pawn) else {
+            continue;
+        };
+        let working_on = q_work_orders.get(pawn).ok().and_then(WorkOrder::job_target);
+        if working_on != Some(job.target) {
+            job_board.release(pawn);
+        }
+    }
+
+    for pawn in job_board.release_expired(time.elapsed_seconds()) {
+        if let Some(mut pawn_commands) = commands.get_entity(pawn) {
+            pawn_commands
+                .clear_work_order()
+                .add_status(PawnStatus::Idle(pawn_status::Idle));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn work_idle_pawns(
     mut commands: Commands,
-    mut q_pawns: Query<
-        (Entity, &Transform, &CarriedResources, &PawnStatus),
-        (With<Pawn>, Without<WorkOrder>, Without<Enemy>),
+    q_pawns: Query<
+        (
+            Entity,
+            &Pawn,
+            &Transform,
+            &CarriedResources,
+            &PawnStatus,
+            &WorkPriorities,
+        ),
+        (Without<WorkOrder>, Without<Enemy>),
     >,
-    q_stones: Query<Entity, With<StoneKind>>,
     q_factory: Query<&GlobalTransform, (With<Factory>, With<Placed>)>,
-    q_placeable: Query<(Entity, &PlaceableType)>,
-    (navmesh, mut work_queue): (Res<Navmesh>, ResMut<super::WorkQueue>),
+    q_placeable: Query<(&PlaceableType, Option<&Health>)>,
+    (regions, mut job_board): (Res<NavmeshRegions>, ResMut<JobBoard>),
     mut pathfinding_event_writer: EventWriter<PathfindRequest>,
     game_resources: Res<GameResources>,
+    time: Res<Time>,
 ) {
-    let navmesh_tiles = &navmesh.0;
     let Ok(factory_transform) = q_factory.get_single() else {
         return;
     };
 
-    fn check_for_stones(
-        entity_set: &HashSet<Entity>,
-        q_stones: &Query<Entity, With<StoneKind>>,
-    ) -> (bool, Option<Entity>) {
-        for entity in entity_set.iter() {
-            if q_stones.get(*entity).is_ok() {
-                return (true, Some(*entity));
-            }
+    for (entity, pawn, transform, resources, status, priorities) in &q_pawns {
+        if !variant_eq(status, &PawnStatus::Idle(Idle)) {
+            continue;
         }
-        (false, None)
-    }
 
-    for (entity, transform, resources, status) in &mut q_pawns {
-        if !variant_eq(status, &PawnStatus::Idle(Idle)) {
+        // Ranking the board is costly, so an idle pawn only looks for work when its search timer comes round
+        if !pawn.search_timer.finished() {
             continue;
         }
 
         let pawn_grid_location = transform.translation.world_pos_to_tile();
-        // check build queue first
-
-        if let Some(placeable_entity) = work_queue.build_queue.front() {
-            // If this if statement fails, then the placeable doesn't exist.
-            // Odd, but move on and continue issuing commands
-            if let Ok((_, placeable_type)) = q_placeable.get(*placeable_entity) {
-                // check if the pawn has enough resources to build the placeable
-                if resources.0 + game_resources.stone > placeable_type.get_missing_resource_count()
-                {
-                    commands.entity(entity).add_work_order(WorkOrder::BuildItem(
-                        work_order::BuildItem {
-                            item_entity: work_queue.build_queue.pop_front().unwrap(),
-                        },
-                    ));
+        let full = resources.0 >= MAX_RESOURCES;
 
-                    continue;
+        let best_job = job_board.best_job(pawn_grid_location, priorities, |job, job_location| {
+            if !regions.can_reach(Faction::Colonist, job_location, pawn_grid_location) {
+                return false;
+            }
+            match job.kind {
+                // building and repairing need stone, either carried by the pawn or waiting at the factory
+                JobKind::Build | JobKind::Repair => {
+                    q_placeable
+                        .get(job.target)
+                        .is_ok_and(|(placeable, health)| {
+                            resources.0 + game_resources.stone > stone_needed(placeable, health)
+                        })
                 }
-            };
-        }
-
-        // check if the pawn is full on resources
-        if resources.0 >= MAX_RESOURCES {
-            commands
-                .entity(entity)
-                .add_status(PawnStatus::Pathfinding(pawn_status::Pathfinding))
-                .add_work_order(WorkOrder::ReturnToFactory(work_order::ReturnToFactory {}));
+                // a full pawn has to drop its stone off at the factory before it can mine or haul any more
+                JobKind::Mine | JobKind::Haul => !full,
+                JobKind::Deconstruct => true,
+            }
+        });
 
-            let grid_location = transform.translation.world_pos_to_tile();
-            let factory_grid = factory_transform.translation().world_pos_to_tile();
+        let Some((job, job_location)) = best_job else {
+            if full {
+                commands
+                    .entity(entity)
+                    .add_status(PawnStatus::Pathfinding(pawn_status::Pathfinding))
+                    .add_work_order(WorkOrder::ReturnToFactory(work_order::ReturnToFactory {}));
 
-            pathfinding_event_writer.send(PathfindRequest {
-                start: grid_location,
-                end: factory_grid,
-                entity,
-            });
+                pathfinding_event_writer.send(PathfindRequest {
+                    start: pawn_grid_location,
+                    end: factory_transform.translation().world_pos_to_tile(),
+                    entity,
+                    agent_size: PAWN_SIZE,
+                    faction: Faction::Colonist,
+                });
+            }
+            continue;
+        };
 
+        if !job_board.claim(job, entity, time.elapsed_seconds()) {
             continue;
         }
 
-        let grid_x = pawn_grid_location.x as usize;
-        let grid_y = pawn_grid_location.y as usize;
-
-        // search the navmesh for non-walkable tiles, and see if the entities within are in q_stones
-        let stone_location;
-        let stone_entity;
-        let mut search_radius: usize = 1;
-
-        // Find the closest stone to the pawn ensuring that the pawn can reach the stone by pathfinding
-        'base: loop {
-            for x in (grid_x.saturating_sub(search_radius))..=(grid_x + search_radius) {
-                for y in (grid_y.saturating_sub(search_radius))..=(grid_y + search_radius) {
-                    if let Some(tile) = navmesh_tiles.get(x).and_then(|row| row.get(y)) {
-                        let (found, stone_ent) = check_for_stones(&tile.occupied_by, &q_stones);
-
-                        if !tile.walkable
-                            && found
-                            && get_pathing(
-                                PathfindRequest {
-                                    start: Vec2::new(x as f32, y as f32),
-                                    end: Vec2::new(grid_x as f32, grid_y as f32),
-                                    entity,
-                                },
-                                &navmesh,
-                            )
-                            .is_some()
-                        {
-                            stone_entity = stone_ent;
-                            stone_location = Some(Vec2::new(x as f32, y as f32));
-                            break 'base;
-                        }
-                    }
-                }
+        match job.kind {
+            JobKind::Mine => {
+                commands
+                    .entity(entity)
+                    .add_status(PawnStatus::Pathfinding(pawn_status::Pathfinding))
+                    .add_work_order(WorkOrder::MineStone(work_order::MineStone {
+                        stone_entity: job.target,
+                    }));
+                pathfinding_event_writer.send(PathfindRequest {
+                    start: pawn_grid_location,
+                    end: job_location,
+                    entity,
+                    agent_size: PAWN_SIZE,
+                    faction: Faction::Colonist,
+                });
+            }
+            JobKind::Haul => {
+                commands
+                    .entity(entity)
+                    .add_status(PawnStatus::Pathfinding(pawn_status::Pathfinding))
+                    .add_work_order(WorkOrder::Haul(work_order::Haul {
+                        loot_entity: job.target,
+                    }));
+                pathfinding_event_writer.send(PathfindRequest {
+                    start: pawn_grid_location,
+                    end: job_location,
+                    entity,
+                    agent_size: PAWN_SIZE,
+                    faction: Faction::Colonist,
+                });
+            }
+            // both systems pathfind to the placeable themselves once the pawn is ready
+            JobKind::Build | JobKind::Repair => {
+                commands.entity(entity).add_work_order(WorkOrder::BuildItem(
+                    work_order::BuildItem {
+                        item_entity: job.target,
+                    },
+                ));
+            }
+            JobKind::Deconstruct => {
+                commands
+                    .entity(entity)
+                    .add_work_order(WorkOrder::DeconstructItem(work_order::DeconstructItem {
+                        item_entity: job.target,
+                    }));
             }
-            search_radius += 1;
-        }
-        if let Some(stone_location) = stone_location {
-            commands
-                .entity(entity)
-                .add_status(PawnStatus::Pathfinding(pawn_status::Pathfinding))
-                .add_work_order(WorkOrder::MineStone(work_order::MineStone {
-                    stone_entity: stone_entity.unwrap(),
-                }));
-            pathfinding_event_writer.send(PathfindRequest {
-                start: pawn_grid_location,
-                end: stone_location,
-                entity,
-            });
         }
     }
 }
@@ -255,11 +453,10 @@ pub fn work_idle_pawns(
 pub fn listen_for_pathfinding_answers(
     mut commands: Commands,
     mut answer_events: EventReader<PathfindAnswer>,
-    mut q_pawns: Query<(&mut Pawn, &mut PawnStatus, Option<&WorkOrder>), With<Pawn>>,
-    mut work_queue: ResMut<WorkQueue>,
+    mut q_pawns: Query<(&mut Pawn, &mut PawnStatus), With<Pawn>>,
 ) {
     for evt in answer_events.read() {
-        let Ok((mut pawn, mut status, work_order)) = q_pawns.get_mut(evt.entity) else {
+        let Ok((mut pawn, mut status)) = q_pawns.get_mut(evt.entity) else {
             continue;
         };
 
@@ -275,20 +472,7 @@ pub fn listen_for_pathfinding_answers(
 
             *status = PawnStatus::Moving(pawn_status::Moving);
         } else {
-            // if we have a work order to build or get stone from factory, add it back to the work queue
-            if let Some(work_order) = work_order {
-                match work_order {
-                    WorkOrder::BuildItem(work_order::BuildItem { item_entity })
-                    | WorkOrder::PickupStoneFromFactory(work_order::PickupStoneFromFactory {
-                        for_entity: item_entity,
-                    }) => {
-                        work_queue.build_queue.push_back(*item_entity);
-                    }
-
-                    _ => {}
-                }
-            }
-
+            // clearing the work order releases its job back onto the job board
             commands
                 .entity(evt.entity)
                 .clear_work_order()
@@ -329,7 +513,9 @@ pub fn move_pawn(
 
         // finally, move the pawns
 
-        let current_grid = transform.translation.world_pos_to_tile();
+        // use the pawn's continuous tile position rather than the floored grid tile so that
+        // diagonal segments are walked in a straight line instead of snapping through a corner tile
+        let current_position = transform.translation.truncate() / TILE_SIZE;
         if pawn.move_to.is_none() {
             pawn.move_to = pawn.move_path.pop_front();
         }
@@ -358,12 +544,31 @@ pub fn move_pawn(
             }
         }
 
-        let direction = (path - current_grid).normalize_or_zero();
-        transform.translation += direction.extend(0.) * MOVE_SPEED * time.delta_seconds();
         pawn.moving = true;
-        if (path - current_grid).length() < 0.2 {
+
+        // steer along the polyline, carrying any distance left over after reaching a waypoint on to the
+        // next segment so the pawn does not stall at every corner
+        let mut position = current_position;
+        let mut target = path;
+        let move_speed = enemy.map_or(MOVE_SPEED, |enemy| enemy.move_speed);
+        let mut remaining = move_speed * time.delta_seconds() / TILE_SIZE;
+        loop {
+            let to_target = target - position;
+            if to_target.length() > remaining {
+                position += to_target.normalize_or_zero() * remaining;
+                break;
+            }
+
+            remaining -= to_target.length();
+            position = target;
             pawn.move_to = pawn.move_path.pop_front();
+            let Some(next) = pawn.move_to else {
+                break;
+            };
+            target = next;
         }
+
+        transform.translation = (position * TILE_SIZE).extend(transform.translation.z);
     }
 
     // for (mut transform, mut pawn, mut facing) in &mut q_pawn.p1() {
@@ -416,6 +621,68 @@ pub fn move_pawn(
     // }
 }
 
+pub fn update_pawn_spatial_index(
+    q_pawns: Query<(Entity, &Transform), With<Pawn>>,
+    mut spatial_index: ResMut<PawnSpatialIndex>,
+) {
+    spatial_index.clear();
+    for (entity, transform) in &q_pawns {
+        let centre = transform.translation.truncate() / TILE_SIZE + Vec2::splat(0.5);
+        spatial_index.insert(entity, centre);
+    }
+}
+
+/// Steer pawns away from any pawn they overlap with, so that groups heading to the same place spread out
+/// and file past each other at chokepoints instead of stacking on a single pixel. Pawns that are standing
+/// still give way half as much as pawns on the move.
+pub fn separate_pawns(
+    mut q_pawns: Query<(Entity, &Pawn, &mut Transform, Option<&Enemy>)>,
+    spatial_index: Res<PawnSpatialIndex>,
+    navmesh: Res<Navmesh>,
+    time: Res<Time>,
+) {
+    for (entity, pawn, mut transform, enemy) in &mut q_pawns {
+        let centre = transform.translation.truncate() / TILE_SIZE + Vec2::splat(0.5);
+
+        let push = spatial_index
+            .nearby(centre, SEPARATION_RADIUS)
+            .filter(|&(other, _)| other != entity)
+            .map(|(other, other_centre)| {
+                let away = (centre - other_centre).try_normalize().unwrap_or_else(|| {
+                    // pawns on exactly the same spot need to agree on which way to split
+                    if entity.index() < other.index() {
+                        Vec2::X
+                    } else {
+                        Vec2::NEG_X
+                    }
+                });
+                away * (1. - centre.distance(other_centre) / SEPARATION_RADIUS)
+            })
+            .sum::<Vec2>();
+
+        if push == Vec2::ZERO {
+            continue;
+        }
+
+        let give_way = if pawn.moving { 1. } else { 0.5 };
+        let offset = push.clamp_length_max(1.) * give_way * SEPARATION_SPEED * time.delta_seconds()
+            / TILE_SIZE;
+
+        // never push a pawn onto a tile it could not walk onto itself
+        let current_tile = GridPos::from_tile_pos_vec(centre);
+        let pushed_tile = GridPos::from_tile_pos_vec(centre + offset);
+        if pushed_tile != current_tile
+            && !navmesh
+                .get(pushed_tile)
+                .is_ok_and(|nav_tile| nav_tile.is_walkable_for(faction_of(enemy)))
+        {
+            continue;
+        }
+
+        transform.translation += (offset * TILE_SIZE).extend(0.);
+    }
+}
+
 // TODO! Fix this function because it doesn't work properly. But it's not a priority right now.
 pub fn update_pawn_animation(
     mut q_pawn: Query<(&mut TextureAtlasSprite, &Pawn, &CharacterFacing), With<Pawn>>,
@@ -430,7 +697,8 @@ pub fn update_pawn_animation(
             // // step forward 4 cells in the texture atlas to reach the next step in the animation
             // sprite.index += 4;
 
-            let final_animation_frame = 15 - *facing as usize;
+            let final_animation_frame =
+                pawn.appearance.gender.frame_rows() * 4 - 1 - *facing as usize;
 
             if sprite.index + 4 > final_animation_frame {
                 sprite.index = *facing as usize;
@@ -441,36 +709,21 @@ pub fn update_pawn_animation(
     }
 }
 
-pub fn update_health_ui(
-    q_pawns: Query<&Pawn>,
-    mut q_health_bar: Query<(&Parent, &mut Sprite), With<HealthBar>>,
+/// Pawns sent somewhere by the player stand and wait once they get there
+pub fn complete_move_orders(
+    mut commands: Commands,
+    q_pawns: Query<(Entity, &Pawn, &WorkOrder, &PawnStatus), Without<Enemy>>,
 ) {
-    let green_health_threshold: usize = 75;
-    let yellow_health_threshold: usize = 50;
-    let red_health_threshold: usize = 25;
-
-    for (parent, mut sprite) in &mut q_health_bar {
-        let pawn_entity = parent.get();
-
-        let Ok(pawn) = q_pawns.get(pawn_entity) else {
+    for (pawn_entity, pawn, work_order, pawn_status) in &q_pawns {
+        let WorkOrder::MoveTo(_) = work_order else {
             continue;
         };
 
-        sprite.custom_size = Some(Vec2::new(
-            pawn.health as f32 / pawn.max_health as f32 * 16.,
-            2.,
-        ));
-
-        if pawn.health == pawn.max_health {
-            sprite.color = Color::NONE;
-        } else if pawn.health > green_health_threshold {
-            sprite.color = Color::GREEN;
-        } else if pawn.health > yellow_health_threshold {
-            sprite.color = Color::YELLOW;
-        } else if pawn.health > red_health_threshold {
-            sprite.color = Color::RED;
-        } else {
-            sprite.color = Color::rgb(0.5, 0., 0.);
+        if !pawn.moving && variant_eq(&PawnStatus::Moving(pawn_status::Moving), pawn_status) {
+            commands
+                .entity(pawn_entity)
+                .clear_work_order()
+                .add_status(PawnStatus::Idle(pawn_status::Idle));
         }
     }
 }
@@ -484,16 +737,26 @@ pub fn mine_stone(
             &mut CarriedResources,
             Option<&WorkOrder>,
             &mut PawnStatus,
+            &mut Skills,
+            &Traits,
         ),
         Without<Enemy>,
     >,
     mut q_stones: Query<(Entity, &mut Stone, &Transform), With<StoneKind>>,
-    mut navmesh: ResMut<Navmesh>,
+    mut navmesh: NavmeshMut,
+    mut game_stats: ResMut<GameStats>,
 ) {
     let mut destroyed_stones = HashSet::<Entity>::default();
 
-    for (pawn_entity, mut pawn, mut carried_resources, work_order, mut pawn_status) in
-        &mut q_pawns_new
+    for (
+        pawn_entity,
+        mut pawn,
+        mut carried_resources,
+        work_order,
+        mut pawn_status,
+        mut skills,
+        traits,
+    ) in &mut q_pawns_new
     {
         // We don't have a mine stone work order, skip this entity.
         let Some(WorkOrder::MineStone(work_order::MineStone { stone_entity })) = work_order else {
@@ -525,6 +788,8 @@ pub fn mine_stone(
             continue;
         }
 
+        let mine_time = work_time(MINE_TIME, &skills.mining, traits);
+        pawn.work_timer.set_duration(mine_time);
         pawn.work_timer.reset();
 
         // If the stone does not exist, then it's been destoyed. Set idle and skip to the next entity.
@@ -537,9 +802,11 @@ pub fn mine_stone(
         };
 
         if stone.remaining_resources > 0 {
-            stone.remaining_resources =
-                stone.remaining_resources.saturating_sub(RESOURCE_GAIN_RATE);
-            carried_resources.0 = carried_resources.0.saturating_add(RESOURCE_GAIN_RATE);
+            let mined = stone.remaining_resources.min(RESOURCE_GAIN_RATE);
+            stone.remaining_resources -= mined;
+            carried_resources.0 = carried_resources.0.saturating_add(mined);
+            game_stats.stone_mined += mined;
+            skills.gain_xp(SkillKind::Mining, MINING_XP, traits);
         } else {
             // we're about to despawn an entity, get it's grid transform and remove it from the navmesh before we despawn it
 
@@ -548,11 +815,15 @@ pub fn mine_stone(
                 continue;
             }
 
-            let stone_grid = stone_transform.translation.world_pos_to_tile();
-            navmesh.0[stone_grid.x as usize][stone_grid.y as usize].walkable = true;
-            navmesh.0[stone_grid.x as usize][stone_grid.y as usize]
-                .occupied_by
-                .remove(&stone_entity);
+            let stone_grid =
+                GridPos::from_tile_pos_vec(stone_transform.translation.world_pos_to_tile());
+            let removed = navmesh.update_tile(stone_grid, |tile| {
+                tile.walkable = true;
+                tile.occupied_by.remove(&stone_entity);
+            });
+            if let Err(error) = removed {
+                warn!("Unable to remove mined stone from the navmesh: {error}");
+            }
 
             commands.entity(stone_entity).despawn_recursive();
             commands
@@ -576,11 +847,10 @@ pub fn pickup_stone_from_factory(
         ),
         Without<Enemy>,
     >,
-    q_placeable: Query<&PlaceableType>,
+    q_placeable: Query<(&PlaceableType, Option<&Health>)>,
     q_factory: Query<&Transform, (With<Factory>, With<Placed>)>,
     mut nav_request: EventWriter<PathfindRequest>,
     mut game_resources: ResMut<GameResources>,
-    mut work_queue: ResMut<WorkQueue>,
 ) {
     for (pawn_entity, mut carried_resources, order, mut status, pawn_transform) in &mut q_pawns {
         // if we aren't picking up stone, we don't need to do anything. Continue to the next entity.
@@ -590,7 +860,7 @@ pub fn pickup_stone_from_factory(
             continue;
         };
 
-        let Ok(placeable) = q_placeable.get(*for_entity) else {
+        let Ok((placeable, health)) = q_placeable.get(*for_entity) else {
             // something went wrong here. Clear work order, set status to idle, and continue to the next entity.
             commands.entity(pawn_entity).clear_work_order();
             *status = PawnStatus::Idle(pawn_status::Idle);
@@ -608,6 +878,8 @@ pub fn pickup_stone_from_factory(
                 start: pawn_transform.translation.world_pos_to_tile(),
                 end: factory_transform.translation.world_pos_to_tile(),
                 entity: pawn_entity,
+                agent_size: PAWN_SIZE,
+                faction: Faction::Colonist,
             });
             continue;
         }
@@ -629,7 +901,7 @@ pub fn pickup_stone_from_factory(
                 item_entity: *for_entity,
             }));
 
-        let required_resources = placeable.get_missing_resource_count();
+        let required_resources = stone_needed(placeable, health);
 
         let mut to_add_to_pawn = std::cmp::min(MAX_RESOURCES, required_resources);
         if to_add_to_pawn > game_resources.stone {
@@ -639,9 +911,8 @@ pub fn pickup_stone_from_factory(
         game_resources.stone -= to_add_to_pawn;
         carried_resources.0 += to_add_to_pawn;
 
-        // edge case. If carried resources is 0, clear work order, set idle, add to work queue, and continue to next entity
+        // edge case. If carried resources is 0, clear work order (releasing the job) and set idle
         if carried_resources.0 == 0 {
-            work_queue.build_queue.push_back(*for_entity);
             commands.entity(pawn_entity).clear_work_order();
             *status = PawnStatus::Idle(pawn_status::Idle);
         }
@@ -658,22 +929,33 @@ pub fn build_placeable(
             &mut CarriedResources,
             &mut PawnStatus,
             &WorkOrder,
+            &mut Skills,
+            &Traits,
         ),
         Without<Enemy>,
     >,
-    mut q_placeable: Query<
-        (Entity, &mut PlaceableType, &Transform),
-        Without<crate::placeable::components::Built>,
-    >,
+    // built placeables are included so that they can be repaired
+    mut q_placeable: Query<(Entity, &mut PlaceableType, &Transform, Option<&mut Health>)>,
     mut nav_request: EventWriter<PathfindRequest>,
 ) {
-    for (entity, transform, mut pawn, mut carried_resources, mut status, order) in &mut q_pawns {
+    for (
+        entity,
+        transform,
+        mut pawn,
+        mut carried_resources,
+        mut status,
+        order,
+        mut skills,
+        traits,
+    ) in &mut q_pawns
+    {
         // If we don't have a build work order, skip this entity
         let WorkOrder::BuildItem(work_order::BuildItem { item_entity }) = order else {
             continue;
         };
 
-        let Ok((_, mut placeable_type, placeable_transform)) = q_placeable.get_mut(*item_entity)
+        let Ok((_, mut placeable_type, placeable_transform, health)) =
+            q_placeable.get_mut(*item_entity)
         else {
             // Not sure what happened here, but clear the status and work order and keep working.
             // The placeable does not exist in the query so it's not workable
@@ -686,7 +968,7 @@ pub fn build_placeable(
         if variant_eq(&PawnStatus::Idle(pawn_status::Idle), &status) {
             // we do not have enough resources OR we are not full on resources. We need to get more resources
             // from the factory
-            if carried_resources.0 < placeable_type.get_missing_resource_count()
+            if carried_resources.0 < stone_needed(&placeable_type, health.as_deref())
                 && carried_resources.0 < MAX_RESOURCES
             {
                 *status = PawnStatus::Idle(pawn_status::Idle);
@@ -707,6 +989,8 @@ pub fn build_placeable(
                 start: pawn_grid_pos,
                 end: placeable_grid_pos,
                 entity,
+                agent_size: PAWN_SIZE,
+                faction: Faction::Colonist,
             });
         }
 
@@ -717,30 +1001,64 @@ pub fn build_placeable(
         if distance_to_placeable < 1.5
             && variant_eq(&PawnStatus::Moving(pawn_status::Moving), &status)
         {
-            // we are close enough to start work. Set the status to building and continue to the next entity
+            // we are close enough to start work. Set the status to building and wind up the build
             *status = PawnStatus::Building(pawn_status::Building);
+            pawn.work_timer
+                .set_duration(work_time(BUILD_TIME, &skills.construction, traits));
+            pawn.work_timer.reset();
         }
         // if we are not in the building state, continue to the next entity
         if !variant_eq(&PawnStatus::Building(pawn_status::Building), &status) {
             continue;
         }
 
+        // we are still putting the placeable together, continue to the next entity
+        if !pawn.work_timer.finished() {
+            continue;
+        }
+
         // we are in the building state. Add pawn's resources to the placeable, subtract the resources from the pawn,
         // and depending on if the build is finished set idle or get more resources from the factory.
-        let to_set = (placeable_type.get_current_resources() + carried_resources.0)
-            .clamp(0, placeable_type.get_max_resources());
+        let (spent, finished) = if let Some(mut health) = health {
+            // only built placeables have health, and working on one is repairing it. The stone put in restores
+            // health in proportion to the stone it took to build.
+            let needed = stone_needed(&placeable_type, Some(&health));
+            let spent = carried_resources.0.min(needed);
+            let restored = if spent == needed {
+                health.max.saturating_sub(health.current)
+            } else {
+                spent * health.max / placeable_type.get_max_resources().max(1)
+            };
+            health.current = (health.current + restored).min(health.max);
 
-        let diff = to_set.saturating_sub(placeable_type.get_current_resources());
+            (spent, health.is_full())
+        } else {
+            let to_set = (placeable_type.get_current_resources() + carried_resources.0)
+                .clamp(0, placeable_type.get_max_resources());
+
+            let diff = to_set.saturating_sub(placeable_type.get_current_resources());
+
+            placeable_type.set_current_resources(to_set);
+
+            (
+                diff,
+                placeable_type.get_current_resources() == placeable_type.get_max_resources(),
+            )
+        };
 
-        placeable_type.set_current_resources(to_set);
+        skills.gain_xp(
+            SkillKind::Construction,
+            spent as f32 * CONSTRUCTION_XP,
+            traits,
+        );
 
-        carried_resources.0 = diff;
+        carried_resources.0 -= spent;
 
         // we are either going to factory or getting a new job
         *status = PawnStatus::Idle(pawn_status::Idle);
 
         // if we have finished building, set the status to idle and clear the work order
-        if placeable_type.get_current_resources() == placeable_type.get_max_resources() {
+        if finished {
             commands.entity(entity).clear_work_order();
             continue;
         }
@@ -755,22 +1073,69 @@ pub fn build_placeable(
     }
 }
 
-pub fn return_to_factory(
+pub fn decay_needs(mut q_needs: Query<(&mut Needs, &PawnStatus)>, time: Res<Time>) {
+    let delta = time.delta_seconds();
+
+    for (mut needs, status) in &mut q_needs {
+        if !variant_eq(status, &PawnStatus::Eating(pawn_status::Eating)) {
+            needs.hunger = (needs.hunger - HUNGER_DECAY_RATE * delta).max(0.);
+        }
+        if !variant_eq(status, &PawnStatus::Sleeping(pawn_status::Sleeping)) {
+            needs.rest = (needs.rest - REST_DECAY_RATE * delta).max(0.);
+        }
+        needs.update_mood();
+    }
+}
+
+/// Colonists whose hunger or rest becomes critical drop their work to eat at the factory or sleep where they stand
+pub fn interrupt_for_needs(
     mut commands: Commands,
-    mut q_pawns: Query<
-        (
-            Entity,
-            &Transform,
-            &Pawn,
-            &mut CarriedResources,
-            &mut PawnStatus,
-            &WorkOrder,
-        ),
-        Without<Enemy>,
-    >,
+    q_pawns: Query<(Entity, &Needs, &PawnStatus, Option<&WorkOrder>), Without<Enemy>>,
+) {
+    for (pawn_entity, needs, status, work_order) in &q_pawns {
+        // a colonist that could not find its way waits for `retry_pathfinding` before trying again, otherwise
+        // an unreachable factory would have it ordered to eat every frame
+        if variant_eq(
+            &PawnStatus::PathfindingError(pawn_status::PathfindingError),
+            status,
+        ) {
+            continue;
+        }
+
+        // a colonist in a fight sees it through before seeing to its needs
+        if let Some(
+            WorkOrder::Eat(_)
+            | WorkOrder::Sleep(_)
+            | WorkOrder::AttackPawn(_)
+            | WorkOrder::AttackFactory(_),
+        ) = work_order
+        {
+            continue;
+        }
+
+        // starving is worse than being tired, so hunger is seen to first
+        let order = if needs.hunger_critical() {
+            WorkOrder::Eat(work_order::Eat {})
+        } else if needs.rest_critical() {
+            WorkOrder::Sleep(work_order::Sleep {})
+        } else {
+            continue;
+        };
+
+        commands
+            .entity(pawn_entity)
+            .remove::<ForcedOrder>()
+            .add_work_order(order)
+            .add_status(PawnStatus::Idle(pawn_status::Idle));
+    }
+}
+
+pub fn eat(
+    mut commands: Commands,
+    mut q_pawns: Query<EatingPawn, Without<Enemy>>,
     q_factory: Query<&Transform, (With<Factory>, With<Placed>)>,
-    mut resources: ResMut<GameResources>,
     mut pathfinding_event_writer: EventWriter<PathfindRequest>,
+    time: Res<Time>,
 ) {
     let Ok(factory_transform) = q_factory.get_single() else {
         return;
@@ -778,60 +1143,284 @@ pub fn return_to_factory(
 
     let factory_grid = factory_transform.translation.world_pos_to_tile();
 
-    for (pawn_entity, transform, pawn, mut carried_resources, mut pawn_status, work_order) in
-        &mut q_pawns
-    {
-        // We are not returning to factory. Skip this entity.
-        if !variant_eq(
-            &WorkOrder::ReturnToFactory(work_order::ReturnToFactory {}),
-            &work_order,
-        ) {
+    for (pawn_entity, transform, pawn, mut needs, mut pawn_status, work_order) in &mut q_pawns {
+        let WorkOrder::Eat(_) = work_order else {
             continue;
-        }
+        };
 
-        // We are idle, we need to pathfind to the factory and move to the next entity
+        // Food is kept at the factory, so head there first
         if variant_eq(&PawnStatus::Idle(pawn_status::Idle), &pawn_status) {
             *pawn_status = PawnStatus::Pathfinding(pawn_status::Pathfinding);
             pathfinding_event_writer.send(PathfindRequest {
                 start: transform.translation.world_pos_to_tile(),
                 end: factory_grid,
                 entity: pawn_entity,
+                agent_size: PAWN_SIZE,
+                faction: Faction::Colonist,
             });
             continue;
         }
 
-        // We are not longer moving. We have made it to the factory and need to deposit the resources.
-        // After which we will set the status to Idle, clear the work order, and move to the next entity.
         if !pawn.moving && variant_eq(&PawnStatus::Moving(pawn_status::Moving), &pawn_status) {
+            *pawn_status = PawnStatus::Eating(pawn_status::Eating);
+        }
+
+        if !variant_eq(&PawnStatus::Eating(pawn_status::Eating), &pawn_status) {
+            continue;
+        }
+
+        needs.hunger = (needs.hunger + EATING_RATE * time.delta_seconds()).min(Needs::MAX);
+        if needs.hunger >= Needs::MAX {
             *pawn_status = PawnStatus::Idle(pawn_status::Idle);
             commands.entity(pawn_entity).clear_work_order();
-            resources.stone += carried_resources.0;
-            carried_resources.0 = 0;
-            continue;
         }
     }
 }
 
-pub fn listen_for_spawn_pawn_event(
+pub fn sleep(
     mut commands: Commands,
-    pawn_res: Res<MalePawns>,
-    q_factory: Query<&GlobalTransform, (With<Factory>, With<Placed>)>,
-    mut game_resources: ResMut<GameResources>,
-    mut spawn_pawn_event_reader: EventReader<SpawnPawnRequestEvent>,
-    navmesh: Res<Navmesh>,
+    mut q_pawns: Query<(Entity, &mut Needs, &mut PawnStatus, &WorkOrder), Without<Enemy>>,
+    time: Res<Time>,
 ) {
-    let Ok(factory_transform) = q_factory.get_single() else {
-        return;
-    };
+    for (pawn_entity, mut needs, mut pawn_status, work_order) in &mut q_pawns {
+        let WorkOrder::Sleep(_) = work_order else {
+            continue;
+        };
 
-    for _ in spawn_pawn_event_reader.read() {
-        if game_resources.stone >= PAWN_COST {
-            game_resources.stone -= 100;
-        } else {
+        if variant_eq(&PawnStatus::Idle(pawn_status::Idle), &pawn_status) {
+            *pawn_status = PawnStatus::Sleeping(pawn_status::Sleeping);
+        }
+
+        if !variant_eq(&PawnStatus::Sleeping(pawn_status::Sleeping), &pawn_status) {
             continue;
         }
-        spawn_pawn_in_random_location(
-            &mut commands,
+
+        needs.rest = (needs.rest + SLEEPING_RATE * time.delta_seconds()).min(Needs::MAX);
+        if needs.rest >= Needs::MAX {
+            *pawn_status = PawnStatus::Idle(pawn_status::Idle);
+            commands.entity(pawn_entity).clear_work_order();
+        }
+    }
+}
+
+/// Pick up as much of a loot pile as the pawn can carry and take it back to the factory. Whatever is left
+/// stays on the board for the next hauler.
+pub fn haul_loot(
+    mut commands: Commands,
+    mut q_pawns: Query<
+        (
+            Entity,
+            &Pawn,
+            &mut CarriedResources,
+            &mut PawnStatus,
+            &WorkOrder,
+        ),
+        Without<Enemy>,
+    >,
+    mut q_loot_piles: Query<&mut LootPile>,
+) {
+    for (pawn_entity, pawn, mut carried_resources, mut pawn_status, work_order) in &mut q_pawns {
+        let WorkOrder::Haul(work_order::Haul { loot_entity }) = work_order else {
+            continue;
+        };
+
+        // We have not reached the pile yet
+        if pawn.moving || !variant_eq(&PawnStatus::Moving(pawn_status::Moving), &pawn_status) {
+            continue;
+        }
+
+        // Someone else hauled the last of it away before we got here
+        let Ok(mut loot_pile) = q_loot_piles.get_mut(*loot_entity) else {
+            *pawn_status = PawnStatus::Idle(pawn_status::Idle);
+            commands.entity(pawn_entity).clear_work_order();
+            continue;
+        };
+
+        let picked_up = loot_pile
+            .amount
+            .min(MAX_RESOURCES.saturating_sub(carried_resources.0));
+        loot_pile.amount -= picked_up;
+        carried_resources.0 += picked_up;
+
+        if loot_pile.amount == 0 {
+            commands.entity(*loot_entity).despawn_recursive();
+        }
+
+        *pawn_status = PawnStatus::Idle(pawn_status::Idle);
+        commands
+            .entity(pawn_entity)
+            .add_work_order(WorkOrder::ReturnToFactory(work_order::ReturnToFactory {}));
+    }
+}
+
+pub fn return_to_factory(
+    mut commands: Commands,
+    mut q_pawns: Query<
+        (
+            Entity,
+            &Transform,
+            &Pawn,
+            &mut CarriedResources,
+            &mut PawnStatus,
+            &WorkOrder,
+        ),
+        Without<Enemy>,
+    >,
+    q_factory: Query<&Transform, (With<Factory>, With<Placed>)>,
+    mut resources: ResMut<GameResources>,
+    mut pathfinding_event_writer: EventWriter<PathfindRequest>,
+) {
+    let Ok(factory_transform) = q_factory.get_single() else {
+        return;
+    };
+
+    let factory_grid = factory_transform.translation.world_pos_to_tile();
+
+    for (pawn_entity, transform, pawn, mut carried_resources, mut pawn_status, work_order) in
+        &mut q_pawns
+    {
+        // We are not returning to factory. Skip this entity.
+        if !variant_eq(
+            &WorkOrder::ReturnToFactory(work_order::ReturnToFactory {}),
+            &work_order,
+        ) {
+            continue;
+        }
+
+        // We are idle, we need to pathfind to the factory and move to the next entity
+        if variant_eq(&PawnStatus::Idle(pawn_status::Idle), &pawn_status) {
+            *pawn_status = PawnStatus::Pathfinding(pawn_status::Pathfinding);
+            pathfinding_event_writer.send(PathfindRequest {
+                start: transform.translation.world_pos_to_tile(),
+                end: factory_grid,
+                entity: pawn_entity,
+                agent_size: PAWN_SIZE,
+                faction: Faction::Colonist,
+            });
+            continue;
+        }
+
+        // We are not longer moving. We have made it to the factory and need to deposit the resources.
+        // After which we will set the status to Idle, clear the work order, and move to the next entity.
+        if !pawn.moving && variant_eq(&PawnStatus::Moving(pawn_status::Moving), &pawn_status) {
+            *pawn_status = PawnStatus::Idle(pawn_status::Idle);
+            commands.entity(pawn_entity).clear_work_order();
+            resources.stone += carried_resources.0;
+            carried_resources.0 = 0;
+            continue;
+        }
+    }
+}
+
+pub fn deconstruct_placeable(
+    mut commands: Commands,
+    mut q_pawns: Query<DeconstructingPawn, Without<Enemy>>,
+    q_placeable: Query<(&PlaceableType, &Transform, Option<&Built>)>,
+    mut navmesh: NavmeshMut,
+    mut nav_request: EventWriter<PathfindRequest>,
+) {
+    let mut deconstructed = HashSet::<Entity>::default();
+
+    for (entity, transform, mut pawn, mut carried_resources, mut status, order) in &mut q_pawns {
+        // If we don't have a deconstruct work order, skip this entity
+        let WorkOrder::DeconstructItem(work_order::DeconstructItem { item_entity }) = order else {
+            continue;
+        };
+
+        let Ok((placeable, placeable_transform, built)) = q_placeable.get(*item_entity) else {
+            // The placeable is already gone, so there is nothing left to do
+            *status = PawnStatus::Idle(pawn_status::Idle);
+            commands.entity(entity).clear_work_order();
+            continue;
+        };
+        let placeable_grid_pos = placeable_transform.translation.world_pos_to_tile();
+
+        // we are idle, we need to pathfind to the placeable
+        if variant_eq(&PawnStatus::Idle(pawn_status::Idle), &status) {
+            *status = PawnStatus::Pathfinding(pawn_status::Pathfinding);
+            nav_request.send(PathfindRequest {
+                start: transform.translation.world_pos_to_tile(),
+                end: placeable_grid_pos,
+                entity,
+                agent_size: PAWN_SIZE,
+                faction: Faction::Colonist,
+            });
+            continue;
+        }
+
+        let distance_to_placeable =
+            (transform.translation.world_pos_to_tile() - placeable_grid_pos).length();
+        if distance_to_placeable < 1.5
+            && variant_eq(&PawnStatus::Moving(pawn_status::Moving), &status)
+        {
+            *status = PawnStatus::Building(pawn_status::Building);
+        }
+        if !variant_eq(&PawnStatus::Building(pawn_status::Building), &status) {
+            continue;
+        }
+
+        if !pawn.work_timer.finished() {
+            continue;
+        }
+        pawn.work_timer.reset();
+
+        // Another pawn got to it first this frame
+        if !deconstructed.insert(*item_entity) {
+            continue;
+        }
+
+        // the placeable is despawned before `handle_built_removed` could see where it was, so free its
+        // tile here. Unbuilt placeables never blocked the tile in the first place.
+        let tile_pos = GridPos::from_tile_pos_vec(placeable_grid_pos);
+        let removed = if built.is_some() {
+            navmesh.update_tile(tile_pos, |tile| {
+                tile.occupied_by.remove(item_entity);
+                // a turret coming off a wall leaves the wall standing
+                let still_built = tile.occupied_by.iter().any(|&occupant| {
+                    q_placeable
+                        .get(occupant)
+                        .is_ok_and(|(_, _, built)| built.is_some())
+                });
+                if !still_built {
+                    tile.walkable = true;
+                    tile.passable_by.clear();
+                }
+            })
+        } else {
+            navmesh.vacate(tile_pos, *item_entity)
+        };
+        if let Err(error) = removed {
+            warn!("Unable to remove deconstructed placeable from the navmesh: {error}");
+        }
+
+        // the pawn gets back whatever stone went into the placeable
+        carried_resources.0 += placeable.get_current_resources();
+        commands.entity(*item_entity).despawn_recursive();
+        commands.entity(entity).clear_work_order();
+        *status = PawnStatus::Idle(pawn_status::Idle);
+    }
+}
+
+pub fn listen_for_spawn_pawn_event(
+    mut commands: Commands,
+    pawn_res: Res<PawnAppearance>,
+    q_factory: Query<&GlobalTransform, (With<Factory>, With<Placed>)>,
+    mut game_resources: ResMut<GameResources>,
+    mut spawn_pawn_event_reader: EventReader<SpawnPawnRequestEvent>,
+    navmesh: Res<Navmesh>,
+) {
+    let Ok(factory_transform) = q_factory.get_single() else {
+        return;
+    };
+
+    for _ in spawn_pawn_event_reader.read() {
+        if game_resources.stone >= PAWN_COST {
+            game_resources.stone -= 100;
+        } else {
+            continue;
+        }
+        spawn_pawn_in_random_location(
+            &mut commands,
             &pawn_res,
             &mut game_resources,
             factory_transform,
@@ -844,6 +1433,7 @@ pub fn tick_timers(mut q_pawns: Query<&mut Pawn>, time: Res<Time>) {
     for mut pawn in &mut q_pawns {
         pawn.search_timer.tick(time.delta());
         pawn.work_timer.tick(time.delta());
+        pawn.attack_timer.tick(time.delta());
         pawn.animation_timer.tick(time.delta());
         pawn.retry_pathfinding_timer.tick(time.delta());
     }
@@ -856,17 +1446,16 @@ pub fn retry_pathfinding(
         &mut Pawn,
         &Transform,
         &mut PawnStatus,
-        Option<&WorkOrder>,
+        Option<&Enemy>,
     )>,
     q_factory: Query<&GlobalTransform, (With<Factory>, With<Placed>)>,
     mut pathfinding_event_writer: EventWriter<PathfindRequest>,
-    mut work_queue: ResMut<WorkQueue>,
 ) {
     let mut pathfinding_requests = Vec::new();
     let Ok(factory_transform) = q_factory.get_single() else {
         return;
     };
-    for (entity, mut pawn, pawn_transform, mut pawn_status, order) in &mut q_pawns {
+    for (entity, mut pawn, pawn_transform, mut pawn_status, enemy) in &mut q_pawns {
         // The pawn is not in a pathfinding error state, skip this entity
         if !variant_eq(
             &PawnStatus::PathfindingError(pawn_status::PathfindingError),
@@ -883,27 +1472,22 @@ pub fn retry_pathfinding(
 
         *pawn_status = PawnStatus::Idle(pawn_status::Idle);
 
-        if let Some(order) = order {
-            match order {
-                WorkOrder::BuildItem(work_order::BuildItem { item_entity })
-                | WorkOrder::PickupStoneFromFactory(PickupStoneFromFactory {
-                    for_entity: item_entity,
-                }) => {
-                    // requeue the work order so it can be picked up by another pawn.
-                    work_queue.build_queue.push_back(*item_entity);
-                }
-                _ => {}
-            }
-        }
-
+        // give up on the current work order, so its job can be picked up by another pawn
         commands.entity(entity).clear_work_order();
 
+        // enemies go back to the factory flow field, and besiege the factory if they are walled out
+        if enemy.is_some() {
+            continue;
+        }
+
         let pawn_pos = pawn_transform.translation.world_pos_to_tile();
         let factory_pos = factory_transform.translation().world_pos_to_tile();
         pathfinding_requests.push(PathfindRequest {
             start: pawn_pos,
             end: factory_pos,
             entity,
+            agent_size: PAWN_SIZE,
+            faction: Faction::Colonist,
         });
     }
 
@@ -911,11 +1495,36 @@ pub fn retry_pathfinding(
 }
 
 pub fn repath_if_navmesh_changes(
-    mut q_pawns: Query<(Entity, &Pawn, &mut PawnStatus)>,
-    navmesh: Res<Navmesh>,
+    mut q_pawns: Query<(Entity, &Pawn, &Transform, &mut PawnStatus, Option<&Enemy>)>,
+    mut nav_tile_changes: EventReader<NavTileChanged>,
     mut nav_request: EventWriter<PathfindRequest>,
 ) {
-    for (entity, pawn, mut status) in &mut q_pawns {
+    // only tiles that stopped being walkable can block an existing path. A door closes a tile for enemies
+    // without changing it for colonists, so each faction gets its own set of blocked tiles.
+    let changes = nav_tile_changes.read().collect::<Vec<_>>();
+    let blocked_for = |faction: Faction| {
+        changes
+            .iter()
+            .filter(|change| {
+                change.old.is_walkable_for(faction) && !change.new.is_walkable_for(faction)
+            })
+            .map(|change| (change.pos.x, change.pos.y))
+            .collect::<HashSet<_>>()
+    };
+    let colonist_blocked = blocked_for(Faction::Colonist);
+    let enemy_blocked = blocked_for(Faction::Enemy);
+
+    if colonist_blocked.is_empty() && enemy_blocked.is_empty() {
+        return;
+    }
+
+    for (entity, pawn, transform, mut status, enemy) in &mut q_pawns {
+        let faction = faction_of(enemy);
+        let blocked = match faction {
+            Faction::Colonist => &colonist_blocked,
+            Faction::Enemy => &enemy_blocked,
+        };
+
         if pawn.move_path.is_empty() {
             continue;
         }
@@ -927,55 +1536,68 @@ pub fn repath_if_navmesh_changes(
             continue;
         }
 
-        for Vec2 { x, y } in &pawn.move_path {
-            if !navmesh.0[*x as usize][*y as usize].walkable {
-                // we've already verified that the path is not empty, so we can unwrap here
-                let target_location = pawn.move_path.back().unwrap().clone();
-                let current_location = pawn.move_path.front().unwrap().clone();
+        // smoothed paths have long straight segments, so check every tile each segment sweeps through,
+        // starting with the one the pawn is currently walking along
+        let current_position = transform.translation.truncate() / TILE_SIZE;
+        let waypoints = std::iter::once(current_position)
+            .chain(pawn.move_to)
+            .chain(pawn.move_path.iter().copied())
+            .collect::<Vec<_>>();
 
-                *status = PawnStatus::Repathing(pawn_status::Repathing);
+        let segment_blocked = waypoints.windows(2).any(|segment| {
+            segment_tiles(segment[0], segment[1], PAWN_SIZE)
+                .iter()
+                .any(|tile| blocked.contains(tile))
+        });
 
-                // request a path from the current location to the target location
-                nav_request.send(PathfindRequest {
-                    start: current_location,
-                    end: target_location,
-                    entity,
-                });
+        if segment_blocked {
+            // we've already verified that the path is not empty, so we can unwrap here
+            let target_location = *pawn.move_path.back().unwrap();
+            let current_location = transform.translation.world_pos_to_tile();
 
-                break;
-            }
+            *status = PawnStatus::Repathing(pawn_status::Repathing);
+
+            // request a path from the current location to the target location
+            nav_request.send(PathfindRequest {
+                start: current_location,
+                end: target_location,
+                entity,
+                agent_size: PAWN_SIZE,
+                faction,
+            });
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn search_for_attack_target_pawn(
     mut commands: Commands,
-    q_pawns: Query<(Entity, &Pawn, &Transform, Option<&WorkOrder>), Without<Enemy>>,
-    q_enemies: Query<(Entity, &Pawn, &Transform, Option<&WorkOrder>), With<Enemy>>,
+    q_pawns: Query<AttackSearchPawn, Without<Enemy>>,
+    q_enemies: Query<AttackSearchPawn, With<Enemy>>,
     mut pathfinding_event_writer: EventWriter<PathfindRequest>,
-    mut work_queue: ResMut<WorkQueue>,
     navmesh: Res<Navmesh>,
+    navmesh_settings: Res<NavmeshSettings>,
+    regions: Res<NavmeshRegions>,
 ) {
     #[derive(Debug)]
     struct PawnAttacking {
         pawn_entity: Entity,
         pawn_location: Vec2,
+        pawn_faction: Faction,
         target_entity: Entity,
         target_location: Vec2,
     }
+    #[allow(clippy::too_many_arguments)]
     fn find_pawns_to_attack(
-        search_query: &Query<
-            (Entity, &Pawn, &Transform, Option<&WorkOrder>),
-            impl ReadOnlyWorldQuery,
-        >,
-        to_attack_query: &Query<
-            (Entity, &Pawn, &Transform, Option<&WorkOrder>),
-            impl ReadOnlyWorldQuery,
-        >,
+        search_query: &Query<AttackSearchPawn, impl ReadOnlyWorldQuery>,
+        to_attack_query: &Query<AttackSearchPawn, impl ReadOnlyWorldQuery>,
+        search_faction: Faction,
         attack_map: &mut HashMap<Entity, Vec<PawnAttacking>>,
         navmesh: &Res<Navmesh>,
+        navmesh_settings: &Res<NavmeshSettings>,
+        regions: &Res<NavmeshRegions>,
     ) {
-        for (pawn_entity, pawn, transform, work_order) in search_query {
+        for (pawn_entity, pawn, transform, work_order, priorities, forced) in search_query {
             // we already have an attack work order, skip this pawn
             if let Some(WorkOrder::AttackPawn(work_order::AttackPawn { pawn_entity: _ })) =
                 work_order
@@ -983,33 +1605,47 @@ pub fn search_for_attack_target_pawn(
                 continue;
             }
 
+            // pawns sent somewhere by the player keep to their orders
+            if forced {
+                continue;
+            }
+
+            // colonists only stop what they are doing to fight if they would rather fight
+            if priorities.is_some_and(|priorities| !priorities.will_fight(work_order)) {
+                continue;
+            }
+
             if !pawn.search_timer.finished() {
                 continue;
             }
             let pawn_position = transform.world_pos_to_tile();
             let mut results = to_attack_query
                 .iter()
-                .filter(|&(_, _, enemy_pos, _)| {
+                .filter(|&(_, _, enemy_pos, _, _, _)| {
                     let enemy_position = enemy_pos.world_pos_to_tile();
-                    (enemy_position - pawn_position).length() <= ENEMY_TILE_RANGE as f32 && {
-                        let path = get_pathing(
-                            PathfindRequest {
-                                start: pawn_position,
-                                end: enemy_position,
-                                entity: pawn_entity,
+                    (enemy_position - pawn_position).length() <= ENEMY_TILE_RANGE as f32
+                        && regions.can_reach(search_faction, pawn_position, enemy_position)
+                        && find_path_in_range(
+                            navmesh,
+                            navmesh_settings,
+                            pawn_position,
+                            enemy_position,
+                            NavAgent {
+                                size: PAWN_SIZE,
+                                faction: search_faction,
                             },
-                            &navmesh,
-                        );
-                        path.is_some() && path.unwrap().len() <= ENEMY_TILE_RANGE
-                    }
+                            ENEMY_TILE_RANGE,
+                        )
+                        .is_some_and(|path| path.len() <= ENEMY_TILE_RANGE)
                 })
                 .collect::<Vec<_>>();
-            results.sort_by(|&(_, _, a, _), &(_, _, b, _)| {
+            results.sort_by(|&(_, _, a, _, _, _), &(_, _, b, _, _, _)| {
                 let a_distance = (a.world_pos_to_tile() - pawn_position).length();
                 let b_distance = (b.world_pos_to_tile() - pawn_position).length();
                 a_distance.partial_cmp(&b_distance).unwrap()
             });
-            let Some((enemy_entity, _, enemy_transform, _)) = results.into_iter().next() else {
+            let Some((enemy_entity, _, enemy_transform, _, _, _)) = results.into_iter().next()
+            else {
                 continue;
             };
 
@@ -1017,6 +1653,7 @@ pub fn search_for_attack_target_pawn(
                 pawn_entity,
                 target_entity: enemy_entity,
                 pawn_location: pawn_position,
+                pawn_faction: search_faction,
                 target_location: enemy_transform.world_pos_to_tile(),
             };
 
@@ -1034,8 +1671,24 @@ pub fn search_for_attack_target_pawn(
     // A map which contains the target of the attack, and the details about the attack
     let mut attack_map = HashMap::<Entity, Vec<PawnAttacking>>::new();
 
-    find_pawns_to_attack(&q_pawns, &q_enemies, &mut attack_map, &navmesh);
-    find_pawns_to_attack(&q_enemies, &q_pawns, &mut attack_map, &navmesh);
+    find_pawns_to_attack(
+        &q_pawns,
+        &q_enemies,
+        Faction::Colonist,
+        &mut attack_map,
+        &navmesh,
+        &navmesh_settings,
+        &regions,
+    );
+    find_pawns_to_attack(
+        &q_enemies,
+        &q_pawns,
+        Faction::Enemy,
+        &mut attack_map,
+        &navmesh,
+        &navmesh_settings,
+        &regions,
+    );
 
     let nav_requests = attack_map
         .values()
@@ -1045,6 +1698,7 @@ pub fn search_for_attack_target_pawn(
                 |&PawnAttacking {
                      pawn_entity,
                      pawn_location,
+                     pawn_faction,
                      target_location,
                      target_entity,
                  }| {
@@ -1053,6 +1707,8 @@ pub fn search_for_attack_target_pawn(
                             start: pawn_location,
                             end: target_location,
                             entity: pawn_entity,
+                            agent_size: PAWN_SIZE,
+                            faction: pawn_faction,
                         },
                         target_entity,
                     )
@@ -1061,24 +1717,8 @@ pub fn search_for_attack_target_pawn(
         })
         .collect::<Vec<_>>();
 
+    // replacing a colonist's work order with the attack releases whatever job it was working on
     for &(PathfindRequest { entity, .. }, target_entity) in &nav_requests {
-        if let Ok((_, _, _, work_order)) = q_pawns.get(entity) {
-            // handle other work orders here
-            if let Some(order) = work_order {
-                match order {
-                    WorkOrder::BuildItem(work_order::BuildItem { item_entity })
-                    | WorkOrder::PickupStoneFromFactory(PickupStoneFromFactory {
-                        for_entity: item_entity,
-                    }) => {
-                        // requeue the work order so it can be picked up by another pawn.
-                        work_queue.build_queue.push_back(*item_entity);
-                        commands.entity(entity).clear_work_order();
-                    }
-                    _ => {}
-                }
-            }
-        }
-
         commands
             .entity(entity)
             .add_status(PawnStatus::Pathfinding(pawn_status::Pathfinding))
@@ -1090,33 +1730,71 @@ pub fn search_for_attack_target_pawn(
     pathfinding_event_writer.send_batch(nav_requests.into_iter().map(|(r, _)| r));
 }
 
+/// A walkable tile on `edge` that the factory can be reached from, or failing that any walkable tile on it.
+/// Every tile on the edge is tried at most once, so the search always ends.
+fn find_spawn_point(
+    edge: SpawnEdge,
+    navmesh: &Navmesh,
+    regions: &NavmeshRegions,
+    factory_tiles: Option<&[Vec2]>,
+    rng: &mut impl Rng,
+) -> Option<Vec2> {
+    let mut candidates = edge.tiles(SIZE).collect::<Vec<_>>();
+    candidates.shuffle(rng);
+
+    let mut fallback = None;
+    for (x, y) in candidates {
+        if !navmesh
+            .get(GridPos::new(x as i32, y as i32))
+            .is_ok_and(|tile| tile.walkable)
+        {
+            continue;
+        }
+
+        let location = Vec2::new(x as f32, y as f32);
+        if factory_tiles.map_or(true, |tiles| {
+            regions.can_reach_any(Faction::Enemy, location, tiles.iter().copied())
+        }) {
+            return Some(location);
+        }
+        fallback.get_or_insert(location);
+    }
+
+    fallback
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_enemy_pawns(
     mut commands: Commands,
-    mut enemy_wave: ResMut<EnemyWave>,
-    pawn_res: Res<MalePawns>,
-    time: Res<Time>,
+    mut wave_director: ResMut<WaveDirector>,
+    mut wave_arrived_reader: EventReader<WaveArrived>,
+    pawn_res: Res<PawnAppearance>,
     navmesh: Res<Navmesh>,
+    regions: Res<NavmeshRegions>,
+    q_factory: Query<&GlobalTransform, (With<Factory>, With<Placed>)>,
     input: Query<&ActionState<crate::Input>>,
     mouse_position: Res<CursorPosition>,
 ) {
-    let mut spawn_enemy = move |spawn_location: Vec2| {
+    let mut spawn_enemy = move |spawn_location: Vec2, archetype: &EnemyArchetype| {
+        let appearance = Appearance::random(&mut rand::thread_rng());
+        let (red, green, blue) = archetype.tint;
         let pawn_entity = commands
             .spawn(PawnBundle {
                 pawn: Pawn {
                     move_path: VecDeque::new(),
                     move_to: None,
-                    health: 100,
-                    max_health: 100,
                     search_timer: Timer::from_seconds(PAWN_SEARCH_TIMER, TimerMode::Repeating),
                     animation_timer: Timer::from_seconds(0.125, TimerMode::Repeating),
                     work_timer: Timer::from_seconds(0.25, TimerMode::Once),
+                    attack_timer: Timer::from_seconds(ATTACK_TIME, TimerMode::Once),
                     retry_pathfinding_timer: Timer::from_seconds(1., TimerMode::Once),
                     moving: false,
+                    appearance,
                 },
                 character_facing: CharacterFacing::Left,
                 name: Name::new("Enemy"),
                 sprite_bundle: SpriteSheetBundle {
-                    texture_atlas: pawn_res.get_random(),
+                    texture_atlas: pawn_res.get(appearance),
                     transform: Transform::from_translation(Vec3::new(
                         spawn_location.x,
                         spawn_location.y,
@@ -1125,30 +1803,23 @@ pub fn spawn_enemy_pawns(
                     sprite: TextureAtlasSprite {
                         anchor: bevy::sprite::Anchor::BottomLeft,
                         index: CharacterFacing::Left as usize,
-                        color: Color::RED,
+                        color: Color::rgb(red, green, blue),
                         ..default()
                     },
                     ..Default::default()
                 },
+                health: Health::new(archetype.health),
                 pawn_status: PawnStatus::Idle(pawn_status::Idle),
                 resources: CarriedResources(0),
             })
-            .insert(Enemy)
+            .insert(Enemy {
+                attack: archetype.attack,
+                move_speed: archetype.move_speed,
+            })
             .id();
 
         commands
-            .spawn(HealthBundle {
-                health_bar: HealthBar,
-                health_bundle: SpriteBundle {
-                    transform: Transform::from_xyz(16. / 2., 20., 1.),
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(16., 2.)),
-                        color: Color::NONE,
-                        ..default()
-                    },
-                    ..default()
-                },
-            })
+            .spawn(HealthBundle::above(TILE_SIZE, TILE_SIZE))
             .set_parent(pawn_entity);
     };
 
@@ -1158,86 +1829,185 @@ pub fn spawn_enemy_pawns(
 
     if input.just_pressed(crate::Input::DebugSpawnPawn) && mouse_position.0.is_some() {
         #[cfg(debug_assertions)]
-        spawn_enemy(mouse_position.0.unwrap().tile_pos_to_world());
+        spawn_enemy(
+            mouse_position.0.unwrap().tile_pos_to_world(),
+            &EnemyArchetype::default(),
+        );
     }
 
-    enemy_wave.enemy_spawn_timer.tick(time.delta());
+    let factory_tiles = q_factory.get_single().ok().map(|factory_transform| {
+        factory_footprint(factory_transform.translation().world_pos_to_tile()).collect::<Vec<_>>()
+    });
+    let mut rng = rand::thread_rng();
+
+    for WaveArrived(plan) in wave_arrived_reader.read() {
+        for (index, archetype) in plan.enemies.iter().enumerate() {
+            // split the wave evenly between its edges, falling back to the others if an edge is blocked
+            let planned_edge = plan.edges.get(index % plan.edges.len().max(1)).copied();
+            let spawn_location = planned_edge
+                .into_iter()
+                .chain(SpawnEdge::ALL)
+                .find_map(|edge| {
+                    find_spawn_point(edge, &navmesh, &regions, factory_tiles.as_deref(), &mut rng)
+                });
+
+            let Some(spawn_location) = spawn_location else {
+                warn!("No walkable edge tile left to spawn an enemy on");
+                continue;
+            };
+
+            spawn_enemy(spawn_location.tile_pos_to_world(), archetype);
+            wave_director.enemies += 1;
+        }
+    }
+}
 
-    if !enemy_wave.enemy_spawn_timer.just_finished() {
+pub fn enemy_search_for_factory(
+    mut commands: Commands,
+    q_enemy_pawns: Query<(Entity, &PawnStatus), With<Enemy>>,
+    flow_field: Res<FactoryFlowField>,
+) {
+    // enemies can't head for the factory until it has been placed
+    if flow_field.0.is_none() {
         return;
     }
-    enemy_wave.wave += 1;
 
-    for _ in 0..enemy_wave.wave * enemy_wave.enemy_count_multiplier {
-        // get a random boolean true or false
-        let mut rng = rand::thread_rng();
-        let spawn_x = rng.gen_bool(0.5);
+    for (entity, pawn_status) in &q_enemy_pawns {
+        // we are not idle, skip this entity
+        if !variant_eq(&PawnStatus::Idle(pawn_status::Idle), pawn_status) {
+            continue;
+        }
 
-        let spawn_location: Vec2;
+        // no pathfinding needed, the factory flow field already knows the way
+        commands
+            .entity(entity)
+            .add_work_order(WorkOrder::AttackFactory(work_order::AttackFactory {}))
+            .add_status(PawnStatus::Moving(pawn_status::Moving));
+    }
+}
 
-        loop {
-            let temp_location: (usize, usize) = if spawn_x {
-                // randomly choose between 0 or SIZE (left or right)
-                let x: usize = if rng.gen_bool(0.5) { SIZE } else { 0 };
-                let y = rng.gen_range(0..SIZE - 1);
+pub fn follow_factory_flow_field(
+    mut q_enemy_pawns: Query<(&mut Pawn, &Transform, &PawnStatus, &WorkOrder), With<Enemy>>,
+    flow_field: Res<FactoryFlowField>,
+    navmesh: Res<Navmesh>,
+    navmesh_settings: Res<NavmeshSettings>,
+) {
+    let Some(flow_field) = &flow_field.0 else {
+        return;
+    };
 
-                (x, y)
-            } else {
-                let x = rng.gen_range(0..SIZE - 1);
-                let y: usize = if rng.gen_bool(0.5) { SIZE } else { 0 };
-                (x, y)
-            };
+    for (mut pawn, transform, status, order) in &mut q_enemy_pawns {
+        if !variant_eq(
+            &WorkOrder::AttackFactory(work_order::AttackFactory {}),
+            order,
+        ) || !variant_eq(&PawnStatus::Moving(pawn_status::Moving), status)
+        {
+            continue;
+        }
 
-            // check navtile to ensure it's walkable
-            if let Some(NavTileOccupant { walkable, .. }) = navmesh
-                .0
-                .get(temp_location.0)
-                .and_then(|o| o.get(temp_location.1))
-            {
-                if *walkable {
-                    spawn_location = Vec2::new(temp_location.0 as f32, temp_location.1 as f32);
-                    break;
-                }
-            }
+        // still walking to the last tile the field pointed us at
+        if pawn.move_to.is_some() {
+            continue;
         }
 
-        // convert spawn_location to world coordinates
-        let spawn_location = spawn_location.tile_pos_to_world();
-        spawn_enemy(spawn_location);
-        enemy_wave.enemies += 1;
-        // spawn enemy pawn
+        // Only ever step one tile at a time, so a newly built wall changes the route immediately.
+        // Stop once the next step would be onto the factory itself.
+        let current_tile = transform.translation.world_pos_to_tile();
+        pawn.move_path.clear();
+        pawn.move_to = flow_field
+            .next_step(&navmesh, &navmesh_settings, current_tile)
+            .filter(|next| !flow_field.is_goal(*next));
     }
 }
 
-pub fn enemy_search_for_factory(
+/// Enemies with no route to the factory because it has been walled in head for the cheapest structure to
+/// break through instead
+pub fn plan_siege(
     mut commands: Commands,
-    mut q_enemy_pawns: Query<(Entity, &Transform, &mut PawnStatus), With<Enemy>>,
+    mut q_enemy_pawns: Query<(Entity, &mut Pawn, &Transform, &PawnStatus, &WorkOrder), With<Enemy>>,
+    q_structures: Query<(), (With<Built>, With<Health>)>,
     q_factory: Query<&GlobalTransform, (With<Factory>, With<Placed>)>,
-    mut nav_request: EventWriter<PathfindRequest>,
+    flow_field: Res<FactoryFlowField>,
+    navmesh: Res<Navmesh>,
+    navmesh_settings: Res<NavmeshSettings>,
 ) {
-    let Ok(factory) = q_factory.get_single() else {
+    let (Some(flow_field), Ok(factory_transform)) = (&flow_field.0, q_factory.get_single()) else {
         return;
     };
+    let footprint =
+        factory_footprint(factory_transform.translation().world_pos_to_tile()).collect::<Vec<_>>();
+
+    let structure_on = |tile: GridPos| {
+        navmesh
+            .get(tile)
+            .ok()
+            .filter(|nav_tile| !nav_tile.is_walkable_for(Faction::Enemy))
+            .and_then(|nav_tile| {
+                nav_tile
+                    .occupied_by
+                    .iter()
+                    .copied()
+                    .find(|&occupant| q_structures.contains(occupant))
+            })
+    };
 
-    for (entity, transform, mut pawn_status) in &mut q_enemy_pawns {
-        // we are not idle, skip this entity
-        if !variant_eq(&PawnStatus::Idle(pawn_status::Idle), &pawn_status) {
+    for (entity, mut pawn, transform, status, order) in &mut q_enemy_pawns {
+        if !variant_eq(
+            &WorkOrder::AttackFactory(work_order::AttackFactory {}),
+            order,
+        ) || !variant_eq(&PawnStatus::Moving(pawn_status::Moving), status)
+        {
             continue;
         }
 
-        let grid_location = transform.translation.world_pos_to_tile();
+        // the flow field still knows the way
+        let current_tile = transform.translation.world_pos_to_tile();
+        if pawn.move_to.is_some() || flow_field.cost(current_tile).is_some() {
+            continue;
+        }
 
-        nav_request.send(PathfindRequest {
-            start: grid_location,
-            end: factory.translation().world_pos_to_tile(),
-            entity,
-        });
+        // searching through the walls is expensive, so only try every so often
+        if !pawn.retry_pathfinding_timer.finished() {
+            continue;
+        }
+        pawn.retry_pathfinding_timer.reset();
 
-        *pawn_status = PawnStatus::Pathfinding(pawn_status::Pathfinding);
+        let Some(path) = find_siege_path(
+            &navmesh,
+            &navmesh_settings,
+            current_tile,
+            footprint.iter().copied(),
+            |tile| structure_on(tile).is_some(),
+        ) else {
+            continue;
+        };
 
+        // walk up to the first structure on the way and break it down. A structure finished under the enemy's
+        // feet is the first one in its way.
+        let own_tile = GridPos::from_tile_pos_vec(current_tile);
+        let Some((structure_index, structure_entity)) = structure_on(own_tile)
+            .map(|structure| (0, structure))
+            .or_else(|| {
+                path.iter().enumerate().skip(1).find_map(|(index, &tile)| {
+                    structure_on(GridPos::from_tile_pos_vec(tile))
+                        .map(|structure| (index, structure))
+                })
+            })
+        else {
+            continue;
+        };
+
+        // the structure is already within reach
+        if structure_index <= 1 {
+            pawn.move_path.clear();
+        } else {
+            pawn.move_path = path[1..structure_index].iter().copied().collect();
+        }
         commands
             .entity(entity)
-            .add_work_order(WorkOrder::AttackFactory(work_order::AttackFactory {}));
+            .add_work_order(WorkOrder::AttackStructure(work_order::AttackStructure {
+                structure_entity,
+            }));
     }
 }
 
@@ -1250,28 +2020,14 @@ pub fn attack_pawn(
         &mut PawnStatus,
         &Transform,
         Option<&Enemy>,
-        &CarriedResources,
+        Option<&mut Skills>,
+        Option<&Traits>,
     )>,
     q_all_pawns: Query<(Entity, &Transform), With<Pawn>>,
-    mut game_resources: ResMut<GameResources>,
-    mut enemy_wave: ResMut<EnemyWave>,
     mut pathfinding_event_writer: EventWriter<PathfindRequest>,
-    mut attack_event_writer: EventWriter<AttackEvent>,
-    mut work_queue: ResMut<WorkQueue>,
-    mut pawn_death_writer: EventWriter<PawnDeath>,
+    mut damage_event_writer: EventWriter<DamageEvent>,
 ) {
-    #[derive(Debug)]
-    struct AttackMetadata {
-        entity: Entity,
-        attacking_entity: Entity,
-        attack_for: usize,
-        entity_is_enemy: bool,
-    }
-
-    let mut queued_attacks: Vec<AttackMetadata> = Vec::new();
-    let mut destroyed_pawns = HashSet::<Entity>::default();
-
-    for (entity, order, mut pawn, mut status, transform, enemy, _) in &mut q_pawns {
+    for (entity, order, mut pawn, mut status, transform, enemy, skills, traits) in &mut q_pawns {
         // we are not set to attack a pawn, skip this entity
         let Some(WorkOrder::AttackPawn(work_order::AttackPawn {
             pawn_entity: attacking_entity,
@@ -1300,6 +2056,8 @@ pub fn attack_pawn(
                     start: transform.translation.world_pos_to_tile(),
                     end: attacking_grid,
                     entity,
+                    agent_size: PAWN_SIZE,
+                    faction: faction_of(enemy),
                 });
             }
             continue;
@@ -1309,99 +2067,171 @@ pub fn attack_pawn(
         *status = PawnStatus::Attacking(pawn_status::Attacking);
 
         // We are still winding up our attack, skip this entity
-        if !pawn.work_timer.finished() {
+        if !pawn.attack_timer.finished() {
             continue;
         }
 
-        // We're about to attack, reset our work timer
-        pawn.work_timer.reset();
+        // We're about to attack, reset our attack timer
+        pawn.attack_timer.reset();
 
-        // queue an attack
-        queued_attacks.push(AttackMetadata {
-            entity,
-            attacking_entity: *attacking_entity,
-            attack_for: if enemy.is_some() {
-                ENEMY_ATTACK_STRENGTH
-            } else {
-                PAWN_ATTACK_STRENGTH
-            },
-            entity_is_enemy: enemy.is_some(),
-        });
+        let damage = match (skills, traits) {
+            (Some(mut skills), Some(traits)) => {
+                let damage =
+                    PAWN_ATTACK_STRENGTH as f32 * skills.melee.multiplier() * traits.damage();
+                skills.gain_xp(SkillKind::Melee, MELEE_XP, traits);
+                damage.round() as usize
+            }
+            _ => enemy.map_or(PAWN_ATTACK_STRENGTH, |enemy| enemy.attack),
+        };
 
-        // Check the attacking_entity to let it know that we are attacking it, and if it's not attacking, make it attack us back
-        // by firing an AttackEvent, which will be listened to and responded to accordingly
-        attack_event_writer.send(AttackEvent {
-            attacker: entity,
+        damage_event_writer.send(DamageEvent {
+            source: entity,
             target: *attacking_entity,
+            amount: damage,
+            damage_type: DamageType::Melee,
         });
     }
+}
 
-    if queued_attacks.is_empty() {
+/// Enemies which have followed the flow field up to the factory hit it until it falls
+pub fn attack_factory(
+    mut q_enemies: Query<AttackingEnemy>,
+    q_factory: Query<(Entity, &GlobalTransform), PlacedFactoryFilter>,
+    mut damage_event_writer: EventWriter<DamageEvent>,
+) {
+    let Ok((factory_entity, factory_transform)) = q_factory.get_single() else {
         return;
+    };
+    let footprint =
+        factory_footprint(factory_transform.translation().world_pos_to_tile()).collect::<Vec<_>>();
+
+    for (entity, mut pawn, mut status, transform, order, enemy) in &mut q_enemies {
+        if !variant_eq(
+            &WorkOrder::AttackFactory(work_order::AttackFactory {}),
+            order,
+        ) {
+            continue;
+        }
+
+        // still walking up to the factory
+        if pawn.moving || pawn.move_to.is_some() {
+            continue;
+        }
+
+        let current_tile = transform.translation.world_pos_to_tile();
+        if !footprint
+            .iter()
+            .any(|tile| tile.distance(current_tile) <= FACTORY_ATTACK_RANGE)
+        {
+            continue;
+        }
+
+        *status = PawnStatus::Attacking(pawn_status::Attacking);
+
+        if !pawn.attack_timer.finished() {
+            continue;
+        }
+        pawn.attack_timer.reset();
+
+        damage_event_writer.send(DamageEvent {
+            source: entity,
+            target: factory_entity,
+            amount: enemy.attack,
+            damage_type: DamageType::Melee,
+        });
     }
+}
 
-    for AttackMetadata {
-        attack_for,
-        attacking_entity,
-        entity,
-        entity_is_enemy,
-        ..
-    } in queued_attacks
-    {
-        let Ok((_, order, mut pawn, _, tx, _, carried_resources)) =
-            q_pawns.get_mut(attacking_entity)
+/// Enemies on a siege hit the structure in their way until it falls, then go back to the factory
+pub fn attack_structure(
+    mut commands: Commands,
+    mut q_enemies: Query<AttackingEnemy>,
+    q_structures: Query<&Transform, StandingStructureFilter>,
+    mut damage_event_writer: EventWriter<DamageEvent>,
+) {
+    for (entity, mut pawn, mut status, transform, order, enemy) in &mut q_enemies {
+        let WorkOrder::AttackStructure(work_order::AttackStructure { structure_entity }) = order
         else {
-            // oops, what happened here? We should have a pawn but we don't. Reset the attacker to idle.
-            commands
-                .entity(attacking_entity)
-                .clear_work_order()
-                .add_status(PawnStatus::Idle(pawn_status::Idle));
             continue;
         };
 
-        // the pawn we're attacking has already been destroyed. Reset the attacker to idle.
-        if destroyed_pawns.contains(&attacking_entity) {
-            commands
-                .entity(entity)
-                .clear_work_order()
-                .add_status(PawnStatus::Idle(pawn_status::Idle));
+        let Ok(structure_transform) = q_structures.get(*structure_entity) else {
+            // it has fallen, the factory flow field will lead us through the gap
+            *status = PawnStatus::Idle(pawn_status::Idle);
+            commands.entity(entity).clear_work_order();
+            continue;
+        };
+
+        // still walking up to the structure
+        if pawn.moving || pawn.move_to.is_some() {
             continue;
         }
 
-        if pawn.health <= attack_for {
-            // whelp, this pawn is about to die. Despawn it and update the game resources
-            // making sure to add it to the destroyed_pawns set so we don't try to attack it again
-            commands.entity(attacking_entity).despawn_recursive();
-            destroyed_pawns.insert(attacking_entity);
-            if entity_is_enemy {
-                game_resources.pawns = game_resources.pawns.saturating_sub(1);
-
-                // check the work order to see if it needs to be requeued
-                if let Some(order) = order {
-                    match order {
-                        WorkOrder::BuildItem(work_order::BuildItem { item_entity })
-                        | WorkOrder::PickupStoneFromFactory(PickupStoneFromFactory {
-                            for_entity: item_entity,
-                        }) => {
-                            // requeue the work order so it can be picked up by another pawn.
-                            work_queue.build_queue.push_back(*item_entity);
-                        }
-                        _ => {}
-                    }
-                }
+        let distance_to_structure = (structure_transform.translation.world_pos_to_tile()
+            - transform.translation.world_pos_to_tile())
+        .length();
+        if distance_to_structure >= 1.5 {
+            // pushed away or the path changed under us, plan the siege again
+            *status = PawnStatus::Idle(pawn_status::Idle);
+            commands.entity(entity).clear_work_order();
+            continue;
+        }
 
-                pawn_death_writer.send(PawnDeath {
-                    pawn: attacking_entity,
-                    killer: entity,
-                    carried_resources: carried_resources.0,
-                    work_order: order.cloned(),
-                    death_location_tile: tx.translation.world_pos_to_tile(),
-                });
-            } else {
-                enemy_wave.enemies = enemy_wave.enemies.saturating_sub(1);
-            }
+        *status = PawnStatus::Attacking(pawn_status::Attacking);
+
+        if !pawn.attack_timer.finished() {
+            continue;
         }
+        pawn.attack_timer.reset();
 
-        pawn.health = pawn.health.saturating_sub(attack_for);
+        damage_event_writer.send(DamageEvent {
+            source: entity,
+            target: *structure_entity,
+            amount: enemy.attack,
+            damage_type: DamageType::Melee,
+        });
     }
 }
+
+/// Despawn the pawns whose health ran out, dropping whatever a colonist was carrying
+#[allow(clippy::too_many_arguments)]
+pub fn handle_destroyed_pawns(
+    mut commands: Commands,
+    mut destroyed_events: EventReader<EntityDestroyed>,
+    q_pawns: Query<DestroyedPawn, With<Pawn>>,
+    mut game_resources: ResMut<GameResources>,
+    mut game_stats: ResMut<GameStats>,
+    mut wave_director: ResMut<WaveDirector>,
+    mut pawn_death_writer: EventWriter<PawnDeath>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for event in destroyed_events.read() {
+        let Ok((order, tx, enemy, carried_resources)) = q_pawns.get(event.entity) else {
+            continue;
+        };
+
+        commands.entity(event.entity).despawn_recursive();
+        if enemy.is_none() {
+            game_resources.pawns = game_resources.pawns.saturating_sub(1);
+            game_stats.pawns_lost += 1;
+            if game_resources.pawns == 0 {
+                game_state.set(GameState::GameOver);
+            }
+
+            pawn_death_writer.send(PawnDeath {
+                pawn: event.entity,
+                killer: event.destroyed_by,
+                carried_resources: carried_resources.0,
+                work_order: order.cloned(),
+                death_location_tile: tx.translation.world_pos_to_tile(),
+            });
+        } else {
+            wave_director.enemies = wave_director.enemies.saturating_sub(1);
+        }
+    }
+}
+
+pub fn reset_pawn_resources(mut commands: Commands) {
+    commands.insert_resource(JobBoard::default());
+    commands.insert_resource(PawnSpatialIndex::default());
+}
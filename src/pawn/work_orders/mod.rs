@@ -1,36 +1,282 @@
+use super::components::pawn_status::{self, AddStatus, PawnStatus};
+use super::components::work_order::{self, AddWorkOrder, ClearWorkOrder, WorkOrder};
+use super::components::{Enemy, ForcedOrder, Pawn};
+use super::systems::PAWN_SIZE;
+use super::{Job, JobBoard, JobKind};
+use crate::navmesh::components::{Faction, Navmesh, PathfindRequest};
+use crate::placeable::components::{Built, MountedOn, NowPlacing};
+use crate::placeable::prelude::PlaceableType;
 use crate::placeable::RequestPlacementEvent;
+use crate::selectable::Selected;
+use crate::stone::Stone;
 use crate::utils::*;
+use crate::{CursorPosition, GameState, WorldInteraction, TILE_SIZE};
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+type PlacedFilter = (With<PlaceableType>, Without<NowPlacing>);
+type UnbuiltFilter = (With<PlaceableType>, Without<Built>, Without<NowPlacing>);
+type SelectedColonistFilter = (With<Selected>, With<Pawn>, Without<Enemy>);
 
 pub struct WorkOrderPlugin;
 
 impl Plugin for WorkOrderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, listen_for_placeable_events);
+        app.add_systems(Update, listen_for_placeable_events)
+            .add_systems(
+                Update,
+                (
+                    designate_deconstruction,
+                    show_deconstruction_designations,
+                    issue_direct_orders,
+                )
+                    .chain()
+                    .run_if(
+                        in_state(GameState::Main).and_then(in_state(WorldInteraction::Selecting)),
+                    ),
+            );
     }
 }
 
 fn listen_for_placeable_events(
     mut commands: Commands,
     mut event_listener: EventReader<RequestPlacementEvent>,
-    mut work_orders: ResMut<super::WorkQueue>,
-    mut navmesh: ResMut<crate::navmesh::Navmesh>,
+    mut navmesh: crate::navmesh::NavmeshMut,
+    q_placeables: Query<&PlaceableType>,
 ) {
     for event in event_listener.read() {
         for placeable in &event.0 {
             let mut placeable = placeable.clone();
-            placeable.sprite_bundle.transform.translation.z = 1.0;
-            placeable.sprite_bundle.sprite.color = Color::rgba(1.0, 1.0, 1.0, 0.5);
+            placeable.sprite_bundle.transform.translation.z = placeable.placeable.z_index();
+            placeable.sprite_bundle.sprite.color = placeable.placeable.tint().with_a(0.5);
 
             let placeable_grid_pos = placeable.sprite_bundle.transform.translation.xy();
             let placeable_grid_pos = GridPos::from_world_pos_vec(placeable_grid_pos);
 
-            let entity = commands.spawn(placeable).id();
-            work_orders.build_queue.push_back(entity);
+            let wall = navmesh.get(placeable_grid_pos).ok().and_then(|tile| {
+                tile.occupied_by.iter().copied().find(|&occupant| {
+                    matches!(q_placeables.get(occupant), Ok(PlaceableType::Wall(_)))
+                })
+            });
+            let mounted_on = wall.filter(|_| placeable.placeable.placeable_on_wall());
+
+            let mut entity_commands = commands.spawn(placeable);
+            if let Some(wall) = mounted_on {
+                entity_commands.insert(MountedOn(wall));
+            }
+            let entity = entity_commands.id();
+
+            if let Err(error) = navmesh.occupy(placeable_grid_pos, entity) {
+                warn!("Unable to add placeable to the navmesh: {error}");
+            }
+        }
+    }
+}
+
+/// Mark the placeable under the cursor to be torn down, or unmark it if it already is
+fn designate_deconstruction(
+    input: Query<&ActionState<crate::Input>>,
+    cursor_position: Res<CursorPosition>,
+    q_placeables: Query<(Entity, &Transform), PlacedFilter>,
+    mut job_board: ResMut<JobBoard>,
+) {
+    let Ok(input) = input.get_single() else {
+        return;
+    };
+
+    if !input.just_pressed(crate::Input::Deconstruct) {
+        return;
+    }
+
+    let Some(cursor_position) = cursor_position.0 else {
+        return;
+    };
+    let cursor_grid = GridPos::from_tile_pos_vec(cursor_position);
+
+    for (entity, transform) in &q_placeables {
+        let placeable_tile = transform.translation.world_pos_to_tile();
+        if GridPos::from_tile_pos_vec(placeable_tile) != cursor_grid {
+            continue;
+        }
+
+        let job = Job::new(JobKind::Deconstruct, entity);
+        if job_board.contains(job) {
+            job_board.remove(job);
+        } else {
+            job_board.post(job, placeable_tile);
+        }
+    }
+}
+
+fn show_deconstruction_designations(job_board: Res<JobBoard>, mut gizmos: Gizmos) {
+    for (_, position) in job_board.jobs_of_kind(JobKind::Deconstruct) {
+        let bottom_left = position.tile_pos_to_world();
+        let top_right = bottom_left + Vec2::splat(TILE_SIZE);
+
+        gizmos.line_2d(bottom_left, top_right, Color::RED);
+        gizmos.line_2d(
+            Vec2::new(bottom_left.x, top_right.y),
+            Vec2::new(top_right.x, bottom_left.y),
+            Color::RED,
+        );
+    }
+}
+
+/// Right clicking with pawns selected orders them to attack the enemy, mine the stone or build the
+/// placeable under the cursor, or otherwise to walk there. Right clicking one of the selected pawns cancels
+/// their orders instead.
+#[allow(clippy::too_many_arguments)]
+fn issue_direct_orders(
+    mut commands: Commands,
+    input: Query<&ActionState<crate::Input>>,
+    cursor_position: Res<CursorPosition>,
+    q_selected: Query<(Entity, &Transform, Has<ForcedOrder>), SelectedColonistFilter>,
+    q_enemies: Query<(Entity, &Transform), With<Enemy>>,
+    q_stones: Query<(Entity, &Transform), With<Stone>>,
+    q_unbuilt: Query<(Entity, &Transform), UnbuiltFilter>,
+    navmesh: Res<Navmesh>,
+    mut job_board: ResMut<JobBoard>,
+    mut pathfinding_event_writer: EventWriter<PathfindRequest>,
+    time: Res<Time>,
+) {
+    let Ok(input) = input.get_single() else {
+        return;
+    };
 
-            navmesh.0[placeable_grid_pos.x as usize][placeable_grid_pos.y as usize]
-                .occupied_by
-                .insert(entity);
+    if !input.just_pressed(crate::Input::Interact) || q_selected.is_empty() {
+        return;
+    }
+
+    let Some(cursor_position) = cursor_position.0 else {
+        return;
+    };
+    let cursor_grid = GridPos::from_tile_pos_vec(cursor_position);
+    let under_cursor = |transform: &Transform| {
+        GridPos::from_tile_pos_vec(transform.translation.world_pos_to_tile()) == cursor_grid
+    };
+
+    if q_selected
+        .iter()
+        .any(|(_, transform, _)| under_cursor(transform))
+    {
+        for (pawn, _, forced) in &q_selected {
+            if !forced {
+                continue;
+            }
+            job_board.release(pawn);
+            commands
+                .entity(pawn)
+                .remove::<ForcedOrder>()
+                .clear_work_order()
+                .add_status(PawnStatus::Idle(pawn_status::Idle));
         }
+        return;
+    }
+
+    // enemies are rarely lined up with a tile, so pick the closest one within a tile of the cursor
+    let cursor_centre = cursor_position + Vec2::splat(0.5);
+    let enemy = q_enemies
+        .iter()
+        .map(|(entity, transform)| {
+            let enemy_centre = transform.translation.truncate() / TILE_SIZE + Vec2::splat(0.5);
+            (entity, enemy_centre.distance(cursor_centre))
+        })
+        .filter(|&(_, distance)| distance <= 1.)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity);
+
+    let (order, job) = if let Some(enemy) = enemy {
+        (
+            WorkOrder::AttackPawn(work_order::AttackPawn { pawn_entity: enemy }),
+            None,
+        )
+    } else if let Some((stone, _)) = q_stones
+        .iter()
+        .find(|(_, transform)| under_cursor(transform))
+    {
+        (
+            WorkOrder::MineStone(work_order::MineStone {
+                stone_entity: stone,
+            }),
+            Some(Job::new(JobKind::Mine, stone)),
+        )
+    } else if let Some((placeable, _)) = q_unbuilt
+        .iter()
+        .find(|(_, transform)| under_cursor(transform))
+    {
+        (
+            WorkOrder::BuildItem(work_order::BuildItem {
+                item_entity: placeable,
+            }),
+            Some(Job::new(JobKind::Build, placeable)),
+        )
+    } else if navmesh
+        .get(cursor_grid)
+        .is_ok_and(|tile| tile.is_walkable_for(Faction::Colonist))
+    {
+        (
+            WorkOrder::MoveTo(work_order::MoveTo {
+                destination: cursor_grid,
+            }),
+            None,
+        )
+    } else {
+        return;
+    };
+
+    let mut pawns = q_selected
+        .iter()
+        .map(|(pawn, transform, _)| (pawn, transform))
+        .collect::<Vec<_>>();
+    // a mining or building job can only be claimed by one pawn, so it goes to the closest one
+    if let Some(job) = job {
+        let closest = pawns
+            .iter()
+            .copied()
+            .min_by(|(_, a), (_, b)| {
+                let a = a
+                    .translation
+                    .world_pos_to_tile()
+                    .distance_squared(cursor_position);
+                let b = b
+                    .translation
+                    .world_pos_to_tile()
+                    .distance_squared(cursor_position);
+                a.total_cmp(&b)
+            })
+            .expect("Selection should not be empty");
+        pawns = vec![closest];
+
+        if let Some(displaced) = job_board.claim_forced(job, closest.0, time.elapsed_seconds()) {
+            commands
+                .entity(displaced)
+                .clear_work_order()
+                .add_status(PawnStatus::Idle(pawn_status::Idle));
+        }
+    }
+
+    for (pawn, transform) in pawns {
+        let mut pawn_commands = commands.entity(pawn);
+        pawn_commands
+            .add_work_order(order.clone())
+            .insert(ForcedOrder);
+
+        // building pathfinds by itself, once it knows whether it has to fetch stone first
+        if let WorkOrder::BuildItem(_) = order {
+            pawn_commands.add_status(PawnStatus::Idle(pawn_status::Idle));
+            continue;
+        }
+
+        pawn_commands.add_status(PawnStatus::Pathfinding(pawn_status::Pathfinding));
+        pathfinding_event_writer.send(PathfindRequest {
+            start: transform.translation.world_pos_to_tile(),
+            end: match &order {
+                WorkOrder::MoveTo(work_order::MoveTo { destination }) => destination.to_vec2(),
+                _ => cursor_grid.to_vec2(),
+            },
+            entity: pawn,
+            agent_size: PAWN_SIZE,
+            faction: Faction::Colonist,
+        });
     }
 }
@@ -1,3 +1,4 @@
+use crate::navmesh::components::Faction;
 use bevy::prelude::*;
 
 #[derive(Component)]
@@ -11,6 +12,14 @@ pub struct TempPlaceholder;
 /// to account for the new entity.
 pub struct Built;
 
+/// A built placeable whose health ran out. It is despawned once it has been taken off the navmesh.
+#[derive(Component)]
+pub struct Destroyed;
+
+/// A placeable put on top of this wall. It comes down with the wall.
+#[derive(Component)]
+pub struct MountedOn(pub Entity);
+
 /// auto create structs and impl PlaceableItem for them.
 /// within struct body, define `placeable_on_wall`, `tileable` and fields.
 /// # Example
@@ -140,9 +149,48 @@ placeables! (
     struct Turret {
         placeable_on_wall: true,
         tileable: false,
+    },
+    struct Door {
+        placeable_on_wall: false,
+        tileable: false,
     }
 );
 
+impl PlaceableType {
+    /// Once built, the placeable blocks the navmesh for everyone except this faction
+    pub fn passable_by(&self) -> Option<Faction> {
+        match self {
+            PlaceableType::Door(_) => Some(Faction::Colonist),
+            _ => None,
+        }
+    }
+
+    /// The `Health` the placeable has once it is built
+    pub fn max_health(&self) -> usize {
+        match self {
+            PlaceableType::Wall(_) => 200,
+            PlaceableType::Door(_) => 150,
+            PlaceableType::Turret(_) => 120,
+        }
+    }
+
+    /// Turrets are drawn over the wall they sit on
+    pub fn z_index(&self) -> f32 {
+        match self {
+            PlaceableType::Turret(_) => 1.5,
+            _ => 1.,
+        }
+    }
+
+    /// Doors share the wall sprite, so they are tinted to tell them apart
+    pub fn tint(&self) -> Color {
+        match self {
+            PlaceableType::Door(_) => Color::rgb(0.65, 0.45, 0.25),
+            _ => Color::WHITE,
+        }
+    }
+}
+
 #[derive(Bundle, Clone)]
 pub struct PlaceableBundle {
     pub placeable: PlaceableType,
@@ -2,7 +2,7 @@ pub mod components;
 mod systems;
 
 use self::components::PlaceableBundle;
-use crate::{utils::GridPos, CursorPosition, WorldInteraction};
+use crate::{utils::GridPos, CursorPosition, GameState, WorldInteraction};
 use bevy::{prelude::*, utils::HashSet};
 
 pub mod prelude {
@@ -17,6 +17,7 @@ impl Plugin for PlaceablePlugin {
             .init_resource::<ZoopStartLocation>()
             .init_resource::<ItemGridPlacement>()
             .add_event::<RequestPlacementEvent>()
+            .add_systems(OnExit(GameState::GameOver), systems::reset_placement)
             .add_systems(
                 Update,
                 systems::show_placeable_item.run_if(resource_changed::<CurrentPlaceableItem>()),
@@ -46,7 +47,12 @@ impl Plugin for PlaceablePlugin {
                     systems::handle_built_removed,
                     systems::add_unbuilt_to_navmesh,
                     systems::check_if_unbuilt_has_been_finished,
+                    systems::drop_unmounted_placeables,
                 ),
+            )
+            .add_systems(
+                Update,
+                systems::handle_destroyed_placeables.after(crate::health::HealthSystemSet),
             );
     }
 }
@@ -1,10 +1,15 @@
 use super::{components::*, RequestPlacementEvent};
+use crate::health::{EntityDestroyed, Health, HealthBundle};
+use crate::navmesh::NavmeshMut;
 use crate::{utils::*, TILE_SIZE};
 use bevy::{prelude::*, utils::hashbrown::HashSet};
 use leafwing_input_manager::prelude::*;
 
 const PLACING_Z_INDEX: f32 = 2.;
 
+/// Placeables still being built which haven't been destroyed on the way
+type UnfinishedFilter = (Without<Built>, Without<Destroyed>);
+
 #[derive(Component)]
 pub struct Cursor;
 
@@ -191,11 +196,9 @@ pub fn populate_item_grid_placement_res_and_send_spawn_event(
         // convert the hashmap above into a Vec of bundles, with the correct transforms applied to them
         let mut bundles = Vec::new();
         for tile_pos in vectors_to_place {
-            // first, ensure tile is walkable
-            let nav_tile = &navmesh.0[tile_pos.x as usize][tile_pos.y as usize];
-            if !nav_tile.walkable {
+            let Ok(nav_tile) = navmesh.get(tile_pos) else {
                 continue;
-            }
+            };
             let nav_tile_has_wall = nav_tile.occupied_by.iter().any(|entity| {
                 // if the entity exists in this query, it's a wall
 
@@ -207,12 +210,27 @@ pub fn populate_item_grid_placement_res_and_send_spawn_event(
                 }
                 return false;
             });
+            let nav_tile_has_other_placeable = nav_tile.occupied_by.iter().any(|entity| {
+                q_walls
+                    .get(*entity)
+                    .is_ok_and(|placeable_item| !matches!(placeable_item, PlaceableType::Wall(_)))
+            });
 
             // if the tile is a wall, and the item is not placeable on a wall, skip it
             if nav_tile_has_wall && !item.placeable.placeable_on_wall() {
                 continue;
             }
 
+            // only one thing can sit on top of a wall
+            if nav_tile_has_other_placeable {
+                continue;
+            }
+
+            // otherwise, ensure tile is walkable. A wall is only in the way of items that can not go on top of it
+            if !nav_tile.walkable && !nav_tile_has_wall {
+                continue;
+            }
+
             let tile_pos_vec = tile_pos.to_vec2();
             let tile_pos_world = tile_pos_vec.tile_pos_to_world();
 
@@ -243,62 +261,146 @@ pub fn populate_item_grid_placement_res_and_send_spawn_event(
 }
 
 pub fn handle_built_added(
-    mut navmesh: ResMut<crate::navmesh::Navmesh>,
-    q_added: Query<(Entity, &GlobalTransform), Added<Built>>,
+    mut commands: Commands,
+    mut navmesh: NavmeshMut,
+    q_added: Query<(Entity, &GlobalTransform, &PlaceableType), Added<Built>>,
 ) {
-    for (entity, transform) in &q_added {
-        let tile_pos = transform.translation().world_pos_to_tile();
-
-        let mesh_item = &mut navmesh.0[tile_pos.x as usize][tile_pos.y as usize];
-
-        mesh_item.occupied_by.insert(entity);
-        mesh_item.walkable = false;
+    for (entity, transform, placeable) in &q_added {
+        commands
+            .entity(entity)
+            .insert(Health::new(placeable.max_health()));
+        commands
+            .spawn(HealthBundle::above(TILE_SIZE, TILE_SIZE))
+            .set_parent(entity);
+
+        let tile_pos = GridPos::from_world_pos_vec(transform.translation().xy());
+
+        let added = navmesh.update_tile(tile_pos, |mesh_item| {
+            mesh_item.occupied_by.insert(entity);
+            mesh_item.walkable = false;
+            mesh_item.passable_by.extend(placeable.passable_by());
+        });
+        if let Err(error) = added {
+            warn!("Unable to add built placeable to the navmesh: {error}");
+        }
     }
 }
 
 pub fn handle_built_removed(
-    mut navmesh: ResMut<crate::navmesh::Navmesh>,
+    mut commands: Commands,
+    mut navmesh: NavmeshMut,
     mut removed_components: RemovedComponents<Built>,
-    q_placeables: Query<(Entity, &GlobalTransform), With<PlaceableType>>,
-    q_built: Query<Entity, With<Built>>,
+    q_placeables: Query<(&GlobalTransform, Has<Destroyed>), With<PlaceableType>>,
+    q_built: Query<(), With<Built>>,
 ) {
     for entity in removed_components.read() {
-        if let Ok((_, transform)) = q_placeables.get(entity) {
-            let tile_pos = transform.translation().world_pos_to_tile();
-
-            let mesh_item = &mut navmesh.0[tile_pos.x as usize][tile_pos.y as usize];
-
-            mesh_item.occupied_by.remove(&entity);
-            // we have a built entity still in this nav tile, we don't want to make it walkable
-            if q_built.get(entity).is_ok() {
-                continue;
+        let Ok((transform, destroyed)) = q_placeables.get(entity) else {
+            continue;
+        };
+
+        let tile_pos = GridPos::from_world_pos_vec(transform.translation().xy());
+        let removed = navmesh.vacate(tile_pos, entity).and_then(|_| {
+            // a turret coming off a wall leaves the wall standing. A wall coming out from under a turret
+            // leaves the tile blocked until `drop_unmounted_placeables` brings the turret down too.
+            let still_built = navmesh.get(tile_pos).is_ok_and(|tile| {
+                tile.occupied_by
+                    .iter()
+                    .any(|&occupant| q_built.contains(occupant))
+            });
+            if still_built {
+                return Ok(());
             }
-            mesh_item.walkable = true;
+            navmesh.update_tile(tile_pos, |mesh_item| {
+                mesh_item.walkable = true;
+                mesh_item.passable_by.clear();
+            })
+        });
+        if let Err(error) = removed {
+            warn!("Unable to remove placeable from the navmesh: {error}");
+        }
+
+        if destroyed {
+            commands.entity(entity).despawn_recursive();
         }
     }
 }
 
 pub fn add_unbuilt_to_navmesh(
-    mut navmesh: ResMut<crate::navmesh::Navmesh>,
+    mut navmesh: NavmeshMut,
     q_unbuilt: Query<(Entity, &GlobalTransform), (Without<Built>, Added<PlaceableType>)>,
 ) {
     for (entity, transform) in &q_unbuilt {
-        let tile_pos = transform.translation().world_pos_to_tile();
-
-        let mesh_item = &mut navmesh.0[tile_pos.x as usize][tile_pos.y as usize];
+        let tile_pos = GridPos::from_world_pos_vec(transform.translation().xy());
 
-        mesh_item.occupied_by.insert(entity);
+        if let Err(error) = navmesh.occupy(tile_pos, entity) {
+            warn!("Unable to add placeable to the navmesh: {error}");
+        }
     }
 }
 
+/// Destroyed placeables still hold all of their stone, so they are skipped rather than built again on their
+/// way to being despawned
 pub fn check_if_unbuilt_has_been_finished(
     mut commands: Commands,
-    mut q_unbuilt: Query<(Entity, &mut Sprite, &PlaceableType), Without<Built>>,
+    mut q_unbuilt: Query<(Entity, &mut Sprite, &PlaceableType), UnfinishedFilter>,
 ) {
     for (entity, mut sprite, placeable) in &mut q_unbuilt {
         if placeable.get_missing_resource_count() == 0 {
-            sprite.color = Color::WHITE;
+            sprite.color = placeable.tint();
             commands.entity(entity).insert(Built);
         }
     }
 }
+
+/// Knock down the placeables whose health ran out. Taking `Built` off hands them to `handle_built_removed`,
+/// which frees their tile before despawning them.
+pub fn handle_destroyed_placeables(
+    mut commands: Commands,
+    mut destroyed_events: EventReader<EntityDestroyed>,
+    q_built: Query<(), (With<PlaceableType>, With<Built>)>,
+) {
+    for event in destroyed_events.read() {
+        if !q_built.contains(event.entity) {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .remove::<Built>()
+            .insert(Destroyed);
+    }
+}
+
+/// Turrets sit on top of walls, so a turret whose wall was torn down or destroyed comes down with it. A built
+/// turret is destroyed, leaving `handle_built_removed` to free its tile, and an unbuilt one is taken off the
+/// navmesh.
+pub fn drop_unmounted_placeables(
+    mut commands: Commands,
+    mut navmesh: NavmeshMut,
+    q_mounted: Query<(Entity, &MountedOn, &GlobalTransform, Has<Built>), Without<Destroyed>>,
+    q_walls: Query<(), (With<PlaceableType>, Without<Destroyed>)>,
+) {
+    for (entity, mounted_on, transform, built) in &q_mounted {
+        if q_walls.contains(mounted_on.0) {
+            continue;
+        }
+
+        if built {
+            commands.entity(entity).remove::<Built>().insert(Destroyed);
+            continue;
+        }
+
+        let tile_pos = GridPos::from_world_pos_vec(transform.translation().xy());
+        if let Err(error) = navmesh.vacate(tile_pos, entity) {
+            warn!("Unable to remove placeable from the navmesh: {error}");
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Drop anything that was being placed when the game was lost
+pub fn reset_placement(mut commands: Commands) {
+    commands.insert_resource(super::CurrentPlaceableItem::default());
+    commands.insert_resource(super::ZoopStartLocation::default());
+    commands.insert_resource(super::ItemGridPlacement::default());
+}
@@ -13,3 +13,10 @@ pub enum StoneKind {
 pub struct Stone {
     pub remaining_resources: usize,
 }
+
+/// Stone dropped on the ground, waiting to be hauled back to the factory. Unlike `Stone` it does not block
+/// the tile it lies on.
+#[derive(Component, Debug)]
+pub struct LootPile {
+    pub amount: usize,
+}
@@ -1,10 +1,11 @@
-use super::{Stone, StoneKind};
+use super::{LootPile, Stone, StoneKind};
 use crate::{
     assets::rocks::{RockAsset, RockCollection},
     utils::*,
     GameState, WorldNoise, PERLIN_DIVIDER, SIZE, TILE_SIZE,
 };
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use noisy_bevy::simplex_noise_2d_seeded;
 
 const MAX_STONE_PER_TILE: usize = 1000;
@@ -85,7 +86,9 @@ pub fn spawn_stone_tiles(
             perlin_location.x = offset_x as f32;
             perlin_location.y = offset_y as f32;
 
-            let nav_tile = &mut navmesh.0[x][y];
+            let Ok(nav_tile) = navmesh.get_mut(GridPos::new(x as i32, y as i32)) else {
+                continue;
+            };
 
             let noise_value =
                 simplex_noise_2d_seeded(perlin_location / PERLIN_DIVIDER, world_noise.seed);
@@ -185,47 +188,47 @@ pub fn update_stone_sprite(
 pub fn listen_for_pawn_death(
     mut commands: Commands,
     mut pawn_death_event: EventReader<crate::pawn::PawnDeath>,
-    mut navmesh: ResMut<crate::navmesh::components::Navmesh>,
+    mut q_loot_piles: Query<(&mut LootPile, &Transform)>,
     rock_collection: Res<RockCollection>,
 ) {
+    // several pawns can die on the same tile in a frame, so gather everything dropped per tile first
+    let mut dropped = HashMap::<GridPos, usize>::new();
     for pawn_death in pawn_death_event.read() {
-        let stone_to_spawn = pawn_death.carried_resources;
+        // we aren't dropping any stone, so move to the next event
+        if pawn_death.carried_resources < 1 {
+            continue;
+        }
+
+        *dropped
+            .entry(GridPos::from_tile_pos_vec(pawn_death.death_location_tile))
+            .or_default() += pawn_death.carried_resources;
+    }
 
-        // we aren't spawning any stone, so move to the next event
-        if stone_to_spawn < 1 {
+    for (grid_pos, amount) in dropped {
+        // stone dropped where there already is a pile is added to it
+        if let Some((mut loot_pile, _)) = q_loot_piles.iter_mut().find(|(_, transform)| {
+            GridPos::from_tile_pos_vec(transform.translation.world_pos_to_tile()) == grid_pos
+        }) {
+            loot_pile.amount += amount;
             continue;
         }
 
-        let spawned_stone = commands
-            .spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::WHITE,
-                        custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
-                        anchor: bevy::sprite::Anchor::BottomLeft,
-                        ..default()
-                    },
-                    texture: rock_collection.red_rock.get_large(),
-                    transform: Transform::from_translation(
-                        pawn_death
-                            .death_location_tile
-                            .tile_pos_to_world()
-                            .extend(0.5),
-                    ),
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::WHITE,
+                    custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                    anchor: bevy::sprite::Anchor::BottomLeft,
                     ..default()
                 },
-                StoneKind::Red,
-                Stone {
-                    remaining_resources: stone_to_spawn,
-                },
-            ))
-            .id();
-
-        // update navmesh to include the new stone
-        let nav_tile = &mut navmesh.0[pawn_death.death_location_tile.x as usize]
-            [pawn_death.death_location_tile.y as usize];
-
-        nav_tile.walkable = false;
-        nav_tile.occupied_by.insert(spawned_stone);
+                texture: rock_collection.red_rock.get_small(),
+                transform: Transform::from_translation(
+                    grid_pos.to_vec2().tile_pos_to_world().extend(0.5),
+                ),
+                ..default()
+            },
+            LootPile { amount },
+            Name::new("Loot Pile"),
+        ));
     }
 }
@@ -1,2 +1,20 @@
 use bevy::prelude::*;
 
+/// The gun mounted on top of a built turret. It turns to face the enemy it is tracking and fires at it
+/// whenever its cooldown is up.
+#[derive(Component)]
+pub struct TurretGun {
+    pub target: Option<Entity>,
+    pub fire_timer: Timer,
+}
+
+#[derive(Component)]
+pub struct Projectile {
+    /// The turret which fired the projectile, credited with the hit
+    pub source: Entity,
+    /// Pixels per second
+    pub velocity: Vec2,
+    pub damage: usize,
+    /// The projectile is despawned once this runs out, whether or not it hit anything
+    pub lifetime: Timer,
+}
@@ -1,12 +1,25 @@
 pub mod components;
 mod systems;
 
+use crate::GameState;
 use bevy::prelude::*;
 
 pub struct TurretPlugin;
 
 impl Plugin for TurretPlugin {
     fn build(&self, app: &mut App) {
-        
+        app.add_systems(
+            Update,
+            (
+                systems::mount_turret_guns,
+                systems::acquire_turret_targets,
+                systems::aim_turret_guns,
+                systems::fire_turret_guns,
+                systems::move_projectiles,
+            )
+                .chain()
+                .before(crate::health::HealthSystemSet)
+                .run_if(in_state(GameState::Main)),
+        );
     }
-}
\ No newline at end of file
+}
@@ -1,2 +1,210 @@
 use super::components::*;
+use crate::assets::turrets::TurretSprites;
+use crate::health::{DamageEvent, DamageType};
+use crate::navmesh::components::Navmesh;
+use crate::pawn::components::Enemy;
+use crate::pawn::PawnSpatialIndex;
+use crate::placeable::components::Built;
+use crate::placeable::prelude::PlaceableType;
+use crate::utils::*;
+use crate::TILE_SIZE;
 use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+/// How far, in tiles, a turret can see and shoot
+const TURRET_RANGE: f32 = 8.;
+/// Seconds between shots
+const TURRET_FIRE_COOLDOWN: f32 = 0.6;
+const TURRET_DAMAGE: usize = 6;
+/// Pixels per second
+const PROJECTILE_SPEED: f32 = 240.;
+/// How close, in tiles, a projectile has to pass to the centre of an enemy to hit it
+const PROJECTILE_HIT_RADIUS: f32 = 0.5;
+/// The gun sprite has its barrel pointing up rather than along the x axis
+const GUN_SPRITE_FACING: f32 = FRAC_PI_2;
+/// How far apart, in tiles, line of sight is sampled
+const LINE_OF_SIGHT_STEP: f32 = 0.25;
+
+/// Whether nothing solid stands between two points given in tile units. The tiles at either end are exempt,
+/// since turrets sit on top of walls.
+fn line_of_sight(navmesh: &Navmesh, from: Vec2, to: Vec2) -> bool {
+    let start = GridPos::from_tile_pos_vec(from);
+    let end = GridPos::from_tile_pos_vec(to);
+    let samples = (from.distance(to) / LINE_OF_SIGHT_STEP).ceil().max(1.) as usize;
+
+    (0..=samples)
+        .map(|sample| GridPos::from_tile_pos_vec(from.lerp(to, sample as f32 / samples as f32)))
+        .filter(|&tile| tile != start && tile != end)
+        .all(|tile| navmesh.get(tile).is_ok_and(|nav_tile| nav_tile.walkable))
+}
+
+/// The centre of a turret, in tile units
+fn turret_centre(transform: &GlobalTransform) -> Vec2 {
+    transform.translation().truncate() / TILE_SIZE + Vec2::splat(0.5)
+}
+
+pub fn mount_turret_guns(
+    mut commands: Commands,
+    q_built: Query<(Entity, &PlaceableType), Added<Built>>,
+    turret_sprites: Res<TurretSprites>,
+) {
+    for (entity, placeable) in &q_built {
+        let PlaceableType::Turret(_) = placeable else {
+            continue;
+        };
+
+        commands
+            .spawn((
+                SpriteBundle {
+                    texture: turret_sprites.gun.clone(),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(TILE_SIZE / 2., TILE_SIZE / 2., 0.1),
+                    ..default()
+                },
+                TurretGun {
+                    target: None,
+                    fire_timer: Timer::from_seconds(TURRET_FIRE_COOLDOWN, TimerMode::Once),
+                },
+                Name::new("Turret Gun"),
+            ))
+            .set_parent(entity);
+    }
+}
+
+/// Keep tracking the current target while it stays in range and in sight, otherwise switch to the nearest
+/// enemy that is
+pub fn acquire_turret_targets(
+    mut q_guns: Query<(&mut TurretGun, &Parent)>,
+    q_turrets: Query<&GlobalTransform, With<Built>>,
+    q_enemies: Query<&Transform, With<Enemy>>,
+    spatial_index: Res<PawnSpatialIndex>,
+    navmesh: Res<Navmesh>,
+) {
+    for (mut gun, parent) in &mut q_guns {
+        let Ok(turret_transform) = q_turrets.get(parent.get()) else {
+            continue;
+        };
+        let turret_position = turret_centre(turret_transform);
+
+        let can_shoot = |enemy_position: Vec2| {
+            enemy_position.distance(turret_position) <= TURRET_RANGE
+                && line_of_sight(&navmesh, turret_position, enemy_position)
+        };
+
+        let keep_target = gun.target.is_some_and(|target| {
+            q_enemies.get(target).is_ok_and(|transform| {
+                can_shoot(transform.translation.truncate() / TILE_SIZE + Vec2::splat(0.5))
+            })
+        });
+        if keep_target {
+            continue;
+        }
+
+        gun.target = spatial_index
+            .nearby(turret_position, TURRET_RANGE)
+            .filter(|&(entity, position)| q_enemies.contains(entity) && can_shoot(position))
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(turret_position)
+                    .total_cmp(&b.distance_squared(turret_position))
+            })
+            .map(|(entity, _)| entity);
+    }
+}
+
+pub fn aim_turret_guns(
+    mut q_guns: Query<(&TurretGun, &mut Transform, &GlobalTransform)>,
+    q_enemies: Query<&Transform, (With<Enemy>, Without<TurretGun>)>,
+) {
+    for (gun, mut transform, global_transform) in &mut q_guns {
+        let Some(Ok(target_transform)) = gun.target.map(|target| q_enemies.get(target)) else {
+            continue;
+        };
+
+        let target_centre = target_transform.translation.truncate() + Vec2::splat(TILE_SIZE / 2.);
+        let to_target = target_centre - global_transform.translation().truncate();
+        transform.rotation =
+            Quat::from_rotation_z(to_target.y.atan2(to_target.x) - GUN_SPRITE_FACING);
+    }
+}
+
+pub fn fire_turret_guns(
+    mut commands: Commands,
+    mut q_guns: Query<(&mut TurretGun, &Parent, &GlobalTransform)>,
+    q_enemies: Query<&Transform, With<Enemy>>,
+    time: Res<Time>,
+) {
+    for (mut gun, parent, global_transform) in &mut q_guns {
+        gun.fire_timer.tick(time.delta());
+
+        let Some(Ok(target_transform)) = gun.target.map(|target| q_enemies.get(target)) else {
+            continue;
+        };
+
+        if !gun.fire_timer.finished() {
+            continue;
+        }
+        gun.fire_timer.reset();
+
+        let muzzle = global_transform.translation();
+        let target_centre = target_transform.translation.truncate() + Vec2::splat(TILE_SIZE / 2.);
+        let direction = (target_centre - muzzle.truncate()).normalize_or_zero();
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::YELLOW,
+                    custom_size: Some(Vec2::splat(3.)),
+                    ..default()
+                },
+                transform: Transform::from_translation(muzzle + Vec3::Z * 0.1),
+                ..default()
+            },
+            Projectile {
+                source: parent.get(),
+                velocity: direction * PROJECTILE_SPEED,
+                damage: TURRET_DAMAGE,
+                lifetime: Timer::from_seconds(
+                    TURRET_RANGE * TILE_SIZE / PROJECTILE_SPEED * 1.5,
+                    TimerMode::Once,
+                ),
+            },
+        ));
+    }
+}
+
+pub fn move_projectiles(
+    mut commands: Commands,
+    mut q_projectiles: Query<(Entity, &mut Projectile, &mut Transform)>,
+    q_enemies: Query<(), With<Enemy>>,
+    spatial_index: Res<PawnSpatialIndex>,
+    mut damage_event_writer: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut projectile, mut transform) in &mut q_projectiles {
+        transform.translation += (projectile.velocity * time.delta_seconds()).extend(0.);
+
+        let position = transform.translation.truncate() / TILE_SIZE;
+        let hit = spatial_index
+            .nearby(position, PROJECTILE_HIT_RADIUS)
+            .find(|&(pawn, _)| q_enemies.contains(pawn));
+
+        if let Some((target, _)) = hit {
+            damage_event_writer.send(DamageEvent {
+                source: projectile.source,
+                target,
+                amount: projectile.damage,
+                damage_type: DamageType::Projectile,
+            });
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        projectile.lifetime.tick(time.delta());
+        if projectile.lifetime.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
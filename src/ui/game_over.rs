@@ -0,0 +1,89 @@
+use super::styles::*;
+use crate::waves::WaveDirector;
+use crate::{GameState, GameStats};
+use bevy::prelude::*;
+use bevy_ui_dsl::*;
+
+pub struct GameOverUIPlugin;
+
+impl Plugin for GameOverUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::GameOver), game_over_ui)
+            .add_systems(OnExit(GameState::GameOver), destroy_game_over_ui)
+            .add_systems(
+                Update,
+                listen_for_restart.run_if(in_state(GameState::GameOver)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct GameOverUI;
+
+#[derive(Component)]
+struct RestartButton;
+
+fn game_over_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    wave_director: Res<WaveDirector>,
+    game_stats: Res<GameStats>,
+) {
+    let mut restart_button = None;
+
+    let root_entity = root(
+        root_full_screen(Some(JustifyContent::Center), Some(AlignItems::Center)),
+        &asset_server,
+        &mut commands,
+        |p| {
+            text("Game Over", c_pixel_text, text_style(Some(56.)), p);
+            text(
+                format!("Waves survived: {}", wave_director.waves_survived()),
+                c_pixel_text,
+                text_style(Some(28.)),
+                p,
+            );
+            text(
+                format!("Stone mined: {}", game_stats.stone_mined),
+                c_pixel_text,
+                text_style(Some(28.)),
+                p,
+            );
+            text(
+                format!("Pawns lost: {}", game_stats.pawns_lost),
+                c_pixel_text,
+                text_style(Some(28.)),
+                p,
+            );
+            button(bordered_button, p, |p| {
+                text("Restart", c_pixel_text, text_style(Some(28.)), p);
+            })
+            .set(&mut restart_button);
+        },
+    );
+
+    commands
+        .entity(restart_button.unwrap())
+        .insert(RestartButton);
+    commands
+        .entity(root_entity)
+        .insert((GameOverUI, Name::new("GameOverUI")));
+}
+
+fn destroy_game_over_ui(mut commands: Commands, query: Query<Entity, With<GameOverUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Leaving `GameState::GameOver` clears the old world, then `build_map` generates a new one
+fn listen_for_restart(
+    restart_button: Query<&Interaction, (With<RestartButton>, Changed<Interaction>)>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in restart_button.iter() {
+        if let Interaction::Pressed = interaction {
+            game_state.set(GameState::WorldSpawn);
+        }
+    }
+}
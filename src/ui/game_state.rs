@@ -1,5 +1,6 @@
 use super::styles::*;
 use crate::placeable::components as placeable_components;
+use crate::waves::{WaveDirector, WaveIncoming};
 use crate::TILE_SIZE;
 use crate::{pawn::SpawnPawnRequestEvent, GameResources, GameState, WorldInteraction};
 use bevy::prelude::*;
@@ -20,10 +21,12 @@ impl Plugin for GameStateUIPlugin {
             )
             .add_systems(
                 Update,
-                update_enemy_counter.run_if(
-                    in_state(GameState::Main)
-                        .and_then(resource_changed::<crate::pawn::EnemyWave>()),
-                ),
+                update_enemy_counter
+                    .run_if(in_state(GameState::Main).and_then(resource_changed::<WaveDirector>())),
+            )
+            .add_systems(
+                Update,
+                (show_wave_warning, hide_wave_warning).run_if(in_state(GameState::Main)),
             )
             .add_systems(
                 Update,
@@ -31,6 +34,7 @@ impl Plugin for GameStateUIPlugin {
                     listen_for_spawn_pawn,
                     listen_for_wall_spawn,
                     listen_for_turret_spawn,
+                    listen_for_door_spawn,
                 )
                     .run_if(in_state(GameState::Main)),
             );
@@ -44,6 +48,10 @@ struct PawnResourceCounter;
 #[derive(Component)]
 struct EnemyResourceCounter;
 
+/// Tells the player where the next wave is coming from, until it arrives
+#[derive(Component)]
+struct WaveWarning(Timer);
+
 #[derive(Component)]
 struct GameStateUI;
 #[derive(Component)]
@@ -55,14 +63,19 @@ struct WallSpawnButton;
 #[derive(Component)]
 struct TurretSpawnButton;
 
+#[derive(Component)]
+struct DoorSpawnButton;
+
 fn game_state_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
     let mut resource_entity = None;
     let mut pawn_entity = None;
     let mut enemy_entity = None;
+    let mut wave_warning_entity = None;
 
     let mut pawn_spawn_button = None;
     let mut wall_spawn_button = None;
     let mut turret_spawn_button = None;
+    let mut door_spawn_button = None;
 
     let root_entity = root(
         root_full_screen(Some(JustifyContent::Center), Some(AlignItems::Center)),
@@ -82,6 +95,7 @@ fn game_state_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                     text("Enemies: ", c_pixel_text, text_style(Some(28.)), p);
                     text("0", c_pixel_text, text_style(Some(28.)), p).set(&mut enemy_entity);
                 });
+                text("", c_pixel_text, text_style(Some(28.)), p).set(&mut wave_warning_entity);
             });
             node(bottom_center_anchor, p, |p| {
                 // pawn spawn button
@@ -101,6 +115,13 @@ fn game_state_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                     |_| {},
                 )
                 .set(&mut turret_spawn_button);
+                // door spawn button
+                button(
+                    spawn_menu_button(Some("objects/walls/wallStone.png")),
+                    p,
+                    |_| {},
+                )
+                .set(&mut door_spawn_button);
             });
         },
     );
@@ -120,6 +141,14 @@ fn game_state_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
         .entity(turret_spawn_button.unwrap())
         .insert(TurretSpawnButton);
+    commands.entity(door_spawn_button.unwrap()).insert((
+        DoorSpawnButton,
+        BackgroundColor(placeable_components::PlaceableType::Door(default()).tint()),
+    ));
+
+    commands
+        .entity(wave_warning_entity.unwrap())
+        .insert((WaveWarning(Timer::default()), Visibility::Hidden));
 
     commands
         .entity(resource_entity.unwrap())
@@ -152,11 +181,42 @@ fn update_pawn_counter(
 }
 
 fn update_enemy_counter(
-    enemy_wave: Res<crate::pawn::EnemyWave>,
+    wave_director: Res<WaveDirector>,
     mut query: Query<&mut Text, With<EnemyResourceCounter>>,
 ) {
     for mut text in &mut query {
-        text.sections[0].value = enemy_wave.enemies.to_string();
+        text.sections[0].value = wave_director.enemies.to_string();
+    }
+}
+
+fn show_wave_warning(
+    mut wave_incoming_reader: EventReader<WaveIncoming>,
+    mut query: Query<(&mut Text, &mut Visibility, &mut WaveWarning)>,
+) {
+    for event in wave_incoming_reader.read() {
+        let edges = event
+            .edges
+            .iter()
+            .map(|edge| edge.name())
+            .collect::<Vec<_>>()
+            .join(" and ");
+
+        for (mut text, mut visibility, mut warning) in &mut query {
+            text.sections[0].value = format!(
+                "Wave {} incoming from the {edges}: {} enemies",
+                event.wave, event.enemies
+            );
+            *visibility = Visibility::Inherited;
+            warning.0 = Timer::from_seconds(event.arrives_in, TimerMode::Once);
+        }
+    }
+}
+
+fn hide_wave_warning(time: Res<Time>, mut query: Query<(&mut Visibility, &mut WaveWarning)>) {
+    for (mut visibility, mut warning) in &mut query {
+        if warning.0.tick(time.delta()).just_finished() {
+            *visibility = Visibility::Hidden;
+        }
     }
 }
 
@@ -200,15 +260,65 @@ fn listen_for_wall_spawn(
     }
 }
 
+fn listen_for_door_spawn(
+    door_spawn_button: Query<&Interaction, (With<DoorSpawnButton>, Changed<Interaction>)>,
+    mut update_world_state: ResMut<NextState<WorldInteraction>>,
+    wall_resource: Res<crate::assets::walls::Wall>,
+    mut placeable_item: ResMut<crate::placeable::CurrentPlaceableItem>,
+) {
+    for interaction in door_spawn_button.iter() {
+        if let Interaction::Pressed = interaction {
+            update_world_state.set(WorldInteraction::Placing);
+
+            let placeable = placeable_components::PlaceableType::Door(placeable_components::Door {
+                max_resources: 60,
+                ..default()
+            });
+
+            placeable_item.0 = Some(placeable_components::PlaceableBundle {
+                sprite_bundle: SpriteBundle {
+                    texture: wall_resource.stone.clone(),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                        anchor: Anchor::BottomLeft,
+                        color: placeable.tint(),
+                        ..default()
+                    },
+                    ..default()
+                },
+                placeable,
+            });
+        }
+    }
+}
+
 fn listen_for_turret_spawn(
     turret_spawn_button: Query<&Interaction, (With<TurretSpawnButton>, Changed<Interaction>)>,
     mut update_world_state: ResMut<NextState<WorldInteraction>>,
+    turret_resource: Res<crate::assets::turrets::TurretSprites>,
     mut placeable_item: ResMut<crate::placeable::CurrentPlaceableItem>,
 ) {
     for interaction in turret_spawn_button.iter() {
         if let Interaction::Pressed = interaction {
             update_world_state.set(WorldInteraction::Placing);
-            // TODO! Spawn a turret here
+
+            placeable_item.0 = Some(placeable_components::PlaceableBundle {
+                sprite_bundle: SpriteBundle {
+                    texture: turret_resource.base.clone(),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                        anchor: Anchor::BottomLeft,
+                        ..default()
+                    },
+                    ..default()
+                },
+                placeable: placeable_components::PlaceableType::Turret(
+                    placeable_components::Turret {
+                        max_resources: 80,
+                        ..default()
+                    },
+                ),
+            });
         }
     }
 }
@@ -1,6 +1,8 @@
 mod factory_state;
+mod game_over;
 mod game_state;
 mod styles;
+mod work_priorities;
 
 use bevy::prelude::*;
 
@@ -11,6 +13,8 @@ impl Plugin for UIPlugin {
         app.add_plugins((
             factory_state::FactoryStateUIPlugin,
             game_state::GameStateUIPlugin,
+            game_over::GameOverUIPlugin,
+            work_priorities::WorkPrioritiesUIPlugin,
         ));
     }
 }
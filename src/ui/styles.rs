@@ -87,3 +87,70 @@ pub fn spawn_menu_button(
         b.border_color = BorderColor(Color::WHITE);
     }
 }
+
+pub fn top_left_anchor(node: &mut NodeBundle) {
+    node.style = Style {
+        display: Display::Flex,
+        flex_direction: FlexDirection::Column,
+        position_type: PositionType::Absolute,
+        top: Val::Px(0.),
+        left: Val::Px(0.),
+        margin: UiRect::all(Val::Px(5.0)),
+        ..default()
+    };
+}
+
+/// A column anchored to the top left, below the buttons that open it
+pub fn top_left_panel(node: &mut NodeBundle) {
+    top_left_anchor(node);
+    node.style.top = Val::Px(60.);
+    node.style.padding = UiRect::all(Val::Px(5.0));
+    node.background_color = BackgroundColor(Color::rgba(0., 0., 0., 0.85));
+}
+
+pub fn table_row(node: &mut NodeBundle) {
+    node.style = Style {
+        display: Display::Flex,
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+}
+
+pub fn table_label(width: f32) -> impl Fn(&mut NodeBundle) {
+    move |node: &mut NodeBundle| {
+        node.style = Style {
+            width: Val::Px(width),
+            display: Display::Flex,
+            justify_content: JustifyContent::Center,
+            ..default()
+        };
+    }
+}
+
+pub fn table_cell_button(_: &AssetServer, b: &mut ButtonBundle) {
+    b.style = Style {
+        width: Val::Px(80.0),
+        height: Val::Px(36.0),
+        display: Display::Flex,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        border: UiRect::all(Val::Px(1.0)),
+        ..default()
+    };
+    b.border_color = BorderColor(Color::WHITE);
+    b.background_color = BackgroundColor(Color::NONE);
+}
+
+pub fn bordered_button(_: &AssetServer, b: &mut ButtonBundle) {
+    b.style = Style {
+        display: Display::Flex,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        padding: UiRect::all(Val::Px(5.0)),
+        border: UiRect::all(Val::Px(1.0)),
+        ..default()
+    };
+    b.border_color = BorderColor(Color::WHITE);
+    b.background_color = BackgroundColor(Color::rgba(0., 0., 0., 0.85));
+}
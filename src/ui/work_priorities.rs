@@ -0,0 +1,192 @@
+use super::styles::*;
+use crate::pawn::components::{WorkPriorities, WorkType};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_ui_dsl::*;
+
+const NAME_COLUMN_WIDTH: f32 = 220.;
+const WORK_TYPE_COLUMN_WIDTH: f32 = 80.;
+
+type WorkPrioritiesNodeFilter = Or<(With<WorkPrioritiesUI>, With<WorkPrioritiesPanel>)>;
+
+pub struct WorkPrioritiesUIPlugin;
+
+impl Plugin for WorkPrioritiesUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShowWorkPriorities>()
+            .add_systems(OnEnter(GameState::Main), work_priorities_toggle_ui)
+            .add_systems(OnExit(GameState::Main), destroy_work_priorities_ui)
+            .add_systems(
+                Update,
+                (
+                    listen_for_work_priorities_toggle,
+                    rebuild_work_priorities_panel,
+                    listen_for_work_priority_cells,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct ShowWorkPriorities(bool);
+
+#[derive(Component)]
+struct WorkPrioritiesUI;
+
+#[derive(Component)]
+struct WorkPrioritiesToggleButton;
+
+#[derive(Component)]
+struct WorkPrioritiesPanel;
+
+/// A button in the grid which cycles one colonist's priority for one type of work
+#[derive(Component)]
+struct WorkPriorityCell {
+    pawn: Entity,
+    work_type: WorkType,
+}
+
+fn work_type_label(work_type: WorkType) -> &'static str {
+    match work_type {
+        WorkType::Mining => "Mine",
+        WorkType::Building => "Build",
+        WorkType::Hauling => "Haul",
+        WorkType::Fighting => "Fight",
+    }
+}
+
+fn priority_label(priority: Option<u8>) -> String {
+    priority.map_or_else(|| "-".to_string(), |priority| priority.to_string())
+}
+
+fn work_priorities_toggle_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut toggle_button = None;
+
+    let root_entity = root(top_left_anchor, &asset_server, &mut commands, |p| {
+        button(bordered_button, p, |p| {
+            text("Work", c_pixel_text, text_style(Some(28.)), p);
+        })
+        .set(&mut toggle_button);
+    });
+
+    commands
+        .entity(toggle_button.unwrap())
+        .insert(WorkPrioritiesToggleButton);
+    commands.entity(root_entity).insert(WorkPrioritiesUI);
+}
+
+fn destroy_work_priorities_ui(
+    mut commands: Commands,
+    query: Query<Entity, WorkPrioritiesNodeFilter>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn listen_for_work_priorities_toggle(
+    toggle_button: Query<&Interaction, (With<WorkPrioritiesToggleButton>, Changed<Interaction>)>,
+    mut show: ResMut<ShowWorkPriorities>,
+) {
+    for interaction in toggle_button.iter() {
+        if let Interaction::Pressed = interaction {
+            show.0 = !show.0;
+        }
+    }
+}
+
+/// The grid has a row per colonist, so it is rebuilt whenever colonists come or go
+fn rebuild_work_priorities_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    show: Res<ShowWorkPriorities>,
+    q_panel: Query<Entity, With<WorkPrioritiesPanel>>,
+    q_colonists: Query<(Entity, &Name, &WorkPriorities)>,
+    q_added_colonists: Query<(), Added<WorkPriorities>>,
+    mut removed_colonists: RemovedComponents<WorkPriorities>,
+) {
+    let colonists_changed = removed_colonists.read().count() > 0 || !q_added_colonists.is_empty();
+    let panel_shown = !q_panel.is_empty();
+    if !colonists_changed && panel_shown == show.0 {
+        return;
+    }
+
+    for panel in &q_panel {
+        commands.entity(panel).despawn_recursive();
+    }
+
+    if !show.0 {
+        return;
+    }
+
+    let mut colonists = q_colonists.iter().collect::<Vec<_>>();
+    colonists.sort_by_key(|&(entity, _, _)| entity);
+
+    let mut cells = Vec::new();
+    let panel = root(top_left_panel, &asset_server, &mut commands, |p| {
+        node(table_row, p, |p| {
+            node(table_label(NAME_COLUMN_WIDTH), p, |_| {});
+            for work_type in WorkType::ALL {
+                node(table_label(WORK_TYPE_COLUMN_WIDTH), p, |p| {
+                    text(
+                        work_type_label(work_type),
+                        c_pixel_text,
+                        text_style(Some(20.)),
+                        p,
+                    );
+                });
+            }
+        });
+
+        for &(pawn, name, priorities) in &colonists {
+            node(table_row, p, |p| {
+                node(table_label(NAME_COLUMN_WIDTH), p, |p| {
+                    text(name.as_str(), c_pixel_text, text_style(Some(20.)), p);
+                });
+                for work_type in WorkType::ALL {
+                    let mut cell = None;
+                    button(table_cell_button, p, |p| {
+                        text(
+                            priority_label(priorities.get(work_type)),
+                            c_pixel_text,
+                            text_style(Some(20.)),
+                            p,
+                        );
+                    })
+                    .set(&mut cell);
+                    cells.push((cell.unwrap(), WorkPriorityCell { pawn, work_type }));
+                }
+            });
+        }
+    });
+
+    commands.entity(panel).insert(WorkPrioritiesPanel);
+    for (cell, work_priority_cell) in cells {
+        commands.entity(cell).insert(work_priority_cell);
+    }
+}
+
+fn listen_for_work_priority_cells(
+    q_cells: Query<(&Interaction, &WorkPriorityCell, &Children), Changed<Interaction>>,
+    mut q_priorities: Query<&mut WorkPriorities>,
+    mut q_text: Query<&mut Text>,
+) {
+    for (interaction, cell, children) in &q_cells {
+        let Interaction::Pressed = interaction else {
+            continue;
+        };
+
+        let Ok(mut priorities) = q_priorities.get_mut(cell.pawn) else {
+            continue;
+        };
+        priorities.cycle(cell.work_type);
+
+        for &child in children.iter() {
+            if let Ok(mut text) = q_text.get_mut(child) {
+                text.sections[0].value = priority_label(priorities.get(cell.work_type));
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use rand::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Everything the wave director needs to know to run a game's worth of waves, loaded from
+/// `assets/enemy.waves.ron`
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct WaveDefinitions {
+    /// Seconds of calm before the first wave
+    pub first_wave_delay: f32,
+    /// How many seconds before a wave arrives the colony is warned about it
+    pub warning_time: f32,
+    pub archetypes: HashMap<String, EnemyArchetype>,
+    /// Played in order. Once they run out, the last wave is repeated while the difficulty keeps climbing.
+    pub waves: Vec<WaveDefinition>,
+    pub difficulty: DifficultyCurve,
+    #[serde(default)]
+    pub breather: Option<Breather>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EnemyArchetype {
+    pub health: usize,
+    pub attack: usize,
+    /// Pixels per second
+    pub move_speed: f32,
+    pub tint: (f32, f32, f32),
+}
+
+impl Default for EnemyArchetype {
+    fn default() -> Self {
+        Self {
+            health: 100,
+            attack: 10,
+            move_speed: 60.,
+            tint: (1., 0., 0.),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WaveDefinition {
+    /// Seconds between the previous wave and this one
+    pub delay: f32,
+    pub groups: Vec<EnemyGroup>,
+    /// The edges the wave may come from. Every edge is allowed if this is empty.
+    #[serde(default)]
+    pub edges: Vec<SpawnEdge>,
+    /// How many of the allowed edges the wave is split between
+    #[serde(default = "one")]
+    pub edge_count: usize,
+}
+
+fn one() -> usize {
+    1
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EnemyGroup {
+    /// A key into `WaveDefinitions::archetypes`
+    pub archetype: String,
+    pub count: usize,
+}
+
+/// How much tougher every wave gets than the one before it. Both growths compound, so `1.1` is ten percent
+/// more each wave.
+#[derive(Deserialize, Debug)]
+pub struct DifficultyCurve {
+    pub count_growth: f32,
+    pub health_growth: f32,
+}
+
+/// A longer lull after every `every`th wave
+#[derive(Deserialize, Debug)]
+pub struct Breather {
+    pub every: usize,
+    /// Seconds added on to the delay before the next wave
+    pub duration: f32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpawnEdge {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl SpawnEdge {
+    pub const ALL: [SpawnEdge; 4] = [
+        SpawnEdge::North,
+        SpawnEdge::South,
+        SpawnEdge::East,
+        SpawnEdge::West,
+    ];
+
+    /// Every tile along this edge of a square map `size` tiles wide
+    pub fn tiles(&self, size: usize) -> impl Iterator<Item = (usize, usize)> {
+        let edge = *self;
+        (0..size).map(move |along| match edge {
+            SpawnEdge::North => (along, size - 1),
+            SpawnEdge::South => (along, 0),
+            SpawnEdge::East => (size - 1, along),
+            SpawnEdge::West => (0, along),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpawnEdge::North => "north",
+            SpawnEdge::South => "south",
+            SpawnEdge::East => "east",
+            SpawnEdge::West => "west",
+        }
+    }
+}
+
+/// A wave with its composition and spawn edges decided, so the warning and the wave itself agree
+#[derive(Debug, Clone)]
+pub struct WavePlan {
+    pub wave: usize,
+    pub enemies: Vec<EnemyArchetype>,
+    pub edges: Vec<SpawnEdge>,
+}
+
+impl WaveDefinitions {
+    /// The seconds between wave `wave - 1` and wave `wave`, counting from 1
+    pub fn delay_before(&self, wave: usize) -> f32 {
+        if wave <= 1 {
+            return self.first_wave_delay;
+        }
+
+        let breather = self
+            .breather
+            .as_ref()
+            .filter(|breather| breather.every > 0 && (wave - 1) % breather.every == 0)
+            .map_or(0., |breather| breather.duration);
+
+        self.definition(wave)
+            .map_or(0., |definition| definition.delay)
+            + breather
+    }
+
+    /// Roll the composition and spawn edges of wave `wave`, counting from 1
+    pub fn plan(&self, wave: usize, rng: &mut impl Rng) -> WavePlan {
+        let Some(definition) = self.definition(wave) else {
+            return WavePlan {
+                wave,
+                enemies: Vec::new(),
+                edges: Vec::new(),
+            };
+        };
+
+        let escalation = wave.saturating_sub(1) as i32;
+        let count_scale = self.difficulty.count_growth.powi(escalation);
+        let health_scale = self.difficulty.health_growth.powi(escalation);
+
+        let mut enemies = Vec::new();
+        for group in &definition.groups {
+            let Some(archetype) = self.archetypes.get(&group.archetype) else {
+                warn!(
+                    "Wave {wave} uses unknown enemy archetype {}",
+                    group.archetype
+                );
+                continue;
+            };
+            let count = (group.count as f32 * count_scale).round() as usize;
+            let health = (archetype.health as f32 * health_scale).round() as usize;
+            enemies.extend((0..count).map(|_| EnemyArchetype {
+                health,
+                ..archetype.clone()
+            }));
+        }
+
+        let allowed = if definition.edges.is_empty() {
+            SpawnEdge::ALL.to_vec()
+        } else {
+            definition.edges.clone()
+        };
+        let edges = allowed
+            .choose_multiple(rng, definition.edge_count.max(1))
+            .copied()
+            .collect();
+
+        WavePlan {
+            wave,
+            enemies,
+            edges,
+        }
+    }
+
+    fn definition(&self, wave: usize) -> Option<&WaveDefinition> {
+        self.waves
+            .get(wave.saturating_sub(1))
+            .or_else(|| self.waves.last())
+    }
+}
+
+#[derive(Default)]
+pub struct WaveDefinitionsLoader;
+
+#[derive(Debug)]
+pub enum WaveDefinitionsLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for WaveDefinitionsLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaveDefinitionsLoaderError::Io(error) => write!(f, "Unable to read waves: {error}"),
+            WaveDefinitionsLoaderError::Ron(error) => write!(f, "Unable to parse waves: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for WaveDefinitionsLoaderError {}
+
+impl AssetLoader for WaveDefinitionsLoader {
+    type Asset = WaveDefinitions;
+    type Settings = ();
+    type Error = WaveDefinitionsLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(WaveDefinitionsLoaderError::Io)?;
+            ron::de::from_bytes(&bytes).map_err(WaveDefinitionsLoaderError::Ron)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
@@ -0,0 +1,61 @@
+pub mod definitions;
+mod systems;
+
+use crate::GameState;
+use bevy::prelude::*;
+
+pub use definitions::{EnemyArchetype, SpawnEdge, WaveDefinitions, WavePlan};
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WaveDefinitions>()
+            .init_asset_loader::<definitions::WaveDefinitionsLoader>()
+            .init_resource::<WaveDirector>()
+            .add_event::<WaveIncoming>()
+            .add_event::<WaveArrived>()
+            .add_systems(OnExit(GameState::GameOver), systems::reset_wave_director)
+            .add_systems(
+                Update,
+                systems::run_wave_director.run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+/// Decides when each wave arrives and what is in it, following the `WaveDefinitions`
+#[derive(Resource, Default)]
+pub struct WaveDirector {
+    /// The last wave to have arrived, counting from 1
+    pub wave: usize,
+    /// Enemies still alive, from every wave so far
+    pub enemies: usize,
+    /// The next wave and the time left until it arrives
+    next: Option<(WavePlan, Timer)>,
+    warned: bool,
+}
+
+impl WaveDirector {
+    /// A wave counts as survived once every enemy in it is dead
+    pub fn waves_survived(&self) -> usize {
+        if self.enemies == 0 {
+            self.wave
+        } else {
+            self.wave.saturating_sub(1)
+        }
+    }
+}
+
+/// Sent `warning_time` seconds before a wave arrives, or as soon as it is planned if it is due sooner
+#[derive(Event, Debug)]
+pub struct WaveIncoming {
+    pub wave: usize,
+    pub enemies: usize,
+    pub edges: Vec<SpawnEdge>,
+    /// Seconds
+    pub arrives_in: f32,
+}
+
+/// The wave is here and its enemies should be spawned
+#[derive(Event, Debug)]
+pub struct WaveArrived(pub WavePlan);
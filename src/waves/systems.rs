@@ -0,0 +1,55 @@
+use super::{WaveArrived, WaveDefinitions, WaveDirector, WaveIncoming};
+use crate::assets::waves::WaveAssets;
+use bevy::prelude::*;
+
+pub fn run_wave_director(
+    director: ResMut<WaveDirector>,
+    wave_assets: Res<WaveAssets>,
+    definitions: Res<Assets<WaveDefinitions>>,
+    time: Res<Time>,
+    mut incoming_writer: EventWriter<WaveIncoming>,
+    mut arrived_writer: EventWriter<WaveArrived>,
+) {
+    let Some(definitions) = definitions.get(&wave_assets.definitions) else {
+        return;
+    };
+    let director = director.into_inner();
+
+    // plan the next wave as soon as the last one has arrived
+    if director.next.is_none() {
+        let wave = director.wave + 1;
+        let plan = definitions.plan(wave, &mut rand::thread_rng());
+        let delay = Timer::from_seconds(definitions.delay_before(wave), TimerMode::Once);
+        director.next = Some((plan, delay));
+        director.warned = false;
+    }
+
+    let Some((plan, timer)) = director.next.as_mut() else {
+        return;
+    };
+    timer.tick(time.delta());
+
+    if !director.warned && timer.remaining_secs() <= definitions.warning_time {
+        director.warned = true;
+        incoming_writer.send(WaveIncoming {
+            wave: plan.wave,
+            enemies: plan.enemies.len(),
+            edges: plan.edges.clone(),
+            arrives_in: timer.remaining_secs(),
+        });
+    }
+
+    if !timer.finished() {
+        return;
+    }
+
+    let Some((plan, _)) = director.next.take() else {
+        return;
+    };
+    director.wave = plan.wave;
+    arrived_writer.send(WaveArrived(plan));
+}
+
+pub fn reset_wave_director(mut commands: Commands) {
+    commands.insert_resource(WaveDirector::default());
+}